@@ -0,0 +1,52 @@
+use std::io::{self, IsTerminal, Stdout, Write};
+
+/// The output a `WindowManager` renders to.
+pub trait Backend {
+    fn writer(&mut self) -> &mut dyn Write;
+
+    /// Whether the output is an interactive terminal rather than e.g. a file or a pipe.
+    fn is_tty(&self) -> bool {
+        true
+    }
+}
+
+/// Renders to stdout.
+#[derive(Debug)]
+pub struct CrosstermBackend {
+    stdout: Stdout,
+}
+
+impl CrosstermBackend {
+    pub fn new() -> Self {
+        CrosstermBackend {
+            stdout: io::stdout(),
+        }
+    }
+}
+
+impl Default for CrosstermBackend {
+    fn default() -> Self {
+        CrosstermBackend::new()
+    }
+}
+
+impl Backend for CrosstermBackend {
+    fn writer(&mut self) -> &mut dyn Write {
+        &mut self.stdout
+    }
+
+    fn is_tty(&self) -> bool {
+        self.stdout.is_terminal()
+    }
+}
+
+/// Collects the output in memory, which is mostly useful for testing.
+impl Backend for Vec<u8> {
+    fn writer(&mut self) -> &mut dyn Write {
+        self
+    }
+
+    fn is_tty(&self) -> bool {
+        false
+    }
+}
@@ -1,16 +1,32 @@
-use crate::{text::{Modifier, Style}, window::Rect};
+use crate::{text::{self, Alignment, Line, Modifier, Style}, window::Rect};
 use crossterm::style::Color;
 use std::cmp::min;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Index, IndexMut, Range};
+use std::sync::Arc;
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cell {
     pub symbol: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::text::serde_color"))]
     pub fg: Color,
+    #[cfg_attr(feature = "serde", serde(with = "crate::text::serde_color"))]
     pub bg: Color,
+    #[cfg_attr(feature = "serde", serde(with = "crate::text::serde_color"))]
+    pub underline_color: Color,
     pub modifier: Modifier,
     pub skip: bool,
+    /// Application defined data attached to the cell, e.g. to find out what was clicked.
+    pub tag: Option<u64>,
+    /// Target of an OSC 8 hyperlink the cell is part of.
+    pub hyperlink: Option<Arc<str>>,
+    /// Opacity of the cell when a window is composited over others, 255 being opaque.
+    pub alpha: u8,
 }
 
 impl Cell {
@@ -36,6 +52,11 @@ impl Cell {
         self
     }
 
+    pub fn set_underline_color(&mut self, color: Color) -> &mut Cell {
+        self.underline_color = color;
+        self
+    }
+
     pub fn set_style(&mut self, style: Style) -> &mut Cell {
         if let Some(c) = style.fg {
             self.fg = c;
@@ -43,6 +64,9 @@ impl Cell {
         if let Some(c) = style.bg {
             self.bg = c;
         }
+        if let Some(c) = style.underline_color {
+            self.underline_color = c;
+        }
         self.modifier.insert(style.add_modifier);
         self.modifier.remove(style.sub_modifier);
         self
@@ -52,6 +76,7 @@ impl Cell {
         Style::default()
             .fg(self.fg)
             .bg(self.bg)
+            .underline_color(self.underline_color)
             .add_modifier(self.modifier)
     }
 
@@ -64,13 +89,56 @@ impl Cell {
         self
     }
 
+    /// Attaches application defined data to the cell. The tag is kept when the symbol or style
+    /// changes and cleared when the cell is reset.
+    pub fn set_tag(&mut self, tag: Option<u64>) -> &mut Cell {
+        self.tag = tag;
+        self
+    }
+
+    /// Makes the cell part of a clickable hyperlink to `url`, for terminals supporting OSC 8.
+    pub fn set_hyperlink(&mut self, url: &str) -> &mut Cell {
+        self.hyperlink = Some(url.into());
+        self
+    }
+
+    pub fn clear_hyperlink(&mut self) -> &mut Cell {
+        self.hyperlink = None;
+        self
+    }
+
+    /// Sets the opacity of the cell. Below 255, its colors are blended with the cell underneath
+    /// when the window is drawn.
+    pub fn set_alpha(&mut self, alpha: u8) -> &mut Cell {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Returns the cell as it looks drawn over `below`, taking its alpha into account.
+    pub fn composite(&self, below: &Cell) -> Cell {
+        if self.alpha == u8::MAX {
+            return self.clone();
+        }
+        let t = self.alpha as f32 / u8::MAX as f32;
+        Cell {
+            fg: text::blend(below.fg, self.fg, t),
+            bg: text::blend(below.bg, self.bg, t),
+            alpha: u8::MAX,
+            ..self.clone()
+        }
+    }
+
     pub fn reset(&mut self) {
         self.symbol.clear();
         self.symbol.push(' ');
         self.fg = Color::Reset;
         self.bg = Color::Reset;
+        self.underline_color = Color::Reset;
         self.modifier = Modifier::empty();
         self.skip = false;
+        self.tag = None;
+        self.hyperlink = None;
+        self.alpha = u8::MAX;
     }
 }
 
@@ -80,17 +148,74 @@ impl Default for Cell {
             symbol: " ".into(),
             fg: Color::Reset,
             bg: Color::Reset,
+            underline_color: Color::Reset,
             modifier: Modifier::empty(),
             skip: false,
+            tag: None,
+            hyperlink: None,
+            alpha: u8::MAX,
         }
     }
 }
 
+/// How wide a zero-width joiner (ZWJ) emoji sequence such as 👨‍👩‍👧 is assumed to be.
+///
+/// A ZWJ sequence is always written into a single cell, but terminals disagree on how many
+/// columns it covers: those that support the sequence draw one emoji, others draw every
+/// component next to each other.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ZwjWidth {
+    /// The width of a single emoji, as reported by `unicode-width`.
+    #[default]
+    Joined,
+    /// The sum of the widths of the components.
+    Split,
+}
+
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Buffer {
     pub width: u16,
     pub height: u16,
+    /// The cells, row by row. Code writing to them directly should call `invalidate_rows` for
+    /// the rows it touched, or `diff` may skip them.
     pub content: Vec<Cell>,
+    pub zwj_width: ZwjWidth,
+    /// The cell `reset` and `resize` fill the buffer with, a blank default cell unless set.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fill_cell: Cell,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    row_hashes: RowHashes,
+}
+
+/// Hash of each row as of the last `Buffer::hash_rows`, `None` for rows written since. It is a
+/// cache only, so it is ignored when comparing or hashing buffers.
+#[derive(Debug, Default, Clone)]
+struct RowHashes(Vec<Option<u64>>);
+
+impl RowHashes {
+    fn get(&self, y: usize) -> Option<u64> {
+        self.0.get(y).copied().flatten()
+    }
+}
+
+fn hash_row(row: &[Cell]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    row.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl PartialEq for RowHashes {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for RowHashes {}
+
+impl Hash for RowHashes {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
 }
 
 impl Buffer {
@@ -100,7 +225,7 @@ impl Buffer {
     }
 
     pub fn filled(width: u16, height: u16, cell: &Cell) -> Buffer {
-        let size = (width * height) as usize;
+        let size = width as usize * height as usize;
         let mut content = Vec::with_capacity(size);
         for _ in 0..size {
             content.push(cell.clone())
@@ -109,39 +234,128 @@ impl Buffer {
             width,
             height,
             content,
+            zwj_width: ZwjWidth::default(),
+            fill_cell: Cell::default(),
+            row_hashes: RowHashes::default(),
+        }
+    }
+
+    /// Creates a buffer just large enough to hold `lines`, one per row, written in `style`. Rows
+    /// of short lines are padded with spaces in the same style.
+    pub fn from_lines(lines: &[&str], style: Style) -> Buffer {
+        let mut cell = Cell::default();
+        cell.set_style(style);
+        let mut buffer = Buffer::filled(0, 0, &cell);
+        let width = lines
+            .iter()
+            .map(|line| {
+                line.graphemes(true)
+                    .map(|g| buffer.grapheme_width(g))
+                    .sum::<usize>()
+            })
+            .max()
+            .unwrap_or(0);
+        let height = lines.len();
+        buffer.resize(
+            min(width, u16::MAX as usize) as u16,
+            min(height, u16::MAX as usize) as u16,
+        );
+        buffer.content.fill(cell);
+        if buffer.width == 0 {
+            return buffer;
+        }
+        for (y, line) in lines.iter().take(buffer.height as usize).enumerate() {
+            buffer.set_string(0, y as u16, line, style);
         }
+        buffer
     }
 
     pub fn resize(&mut self, width: u16, height: u16) {
         self.width = width;
         self.height = height;
         self.content
-            .resize((width * height) as usize, Cell::default());
+            .resize(width as usize * height as usize, self.fill_cell.clone());
+        self.row_hashes.0.clear();
     }
 
-    fn area(&self) -> u16 {
-        self.width * self.height
+    fn area(&self) -> usize {
+        self.width as usize * self.height as usize
     }
 
-    /// Reset all cells in the buffer
+    /// Reset all cells in the buffer to `fill_cell`
     pub fn reset(&mut self) {
         for c in &mut self.content {
-            c.reset();
+            c.clone_from(&self.fill_cell);
         }
+        self.row_hashes.0.clear();
     }
 
-    /// Returns the index in the `Vec<Cell>` for the given global (x, y) coordinates.
-    ///
-    /// Global coordinates are offset by the Buffer's area offset (`x`/`y`).
+    /// Marks `rows` as changed, so `diff` compares their cells again until the next `hash_rows`.
+    pub fn invalidate_rows(&mut self, rows: Range<u16>) {
+        for y in rows {
+            match self.row_hashes.0.get_mut(y as usize) {
+                Some(hash) => *hash = None,
+                None => break,
+            }
+        }
+    }
+
+    /// Hashes every row changed since the last call. Rows whose hashes match in both buffers are
+    /// skipped by `diff` without comparing their cells.
+    pub fn hash_rows(&mut self) {
+        let width = self.width as usize;
+        self.row_hashes.0.resize(self.height as usize, None);
+        for (y, hash) in self.row_hashes.0.iter_mut().enumerate() {
+            if hash.is_none() {
+                *hash = Some(hash_row(&self.content[y * width..(y + 1) * width]));
+            }
+        }
+    }
+
+    /// Returns the hash of row `y`, from the cache kept by `hash_rows` if it is up to date.
+    pub(crate) fn row_hash(&self, y: u16) -> u64 {
+        self.row_hashes.get(y as usize).unwrap_or_else(|| {
+            let start = self.index_of(0, y);
+            hash_row(&self.content[start..start + self.width as usize])
+        })
+    }
+
+    /// Moves every row up by `n` rows, leaving rows of `fill_cell` at the bottom.
+    pub fn scroll_up(&mut self, n: u16) {
+        if n >= self.height {
+            return self.reset();
+        }
+        let shift = n as usize * self.width as usize;
+        self.content.rotate_left(shift);
+        self.row_hashes.0.clear();
+        let len = self.content.len();
+        for c in &mut self.content[len - shift..] {
+            c.clone_from(&self.fill_cell);
+        }
+    }
+
+    /// Moves every row down by `n` rows, leaving rows of `fill_cell` at the top.
+    pub fn scroll_down(&mut self, n: u16) {
+        if n >= self.height {
+            return self.reset();
+        }
+        let shift = n as usize * self.width as usize;
+        self.content.rotate_right(shift);
+        self.row_hashes.0.clear();
+        for c in &mut self.content[..shift] {
+            c.clone_from(&self.fill_cell);
+        }
+    }
+
+    /// Returns the index in the `Vec<Cell>` for the given (x, y) coordinates.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use ratatui::prelude::*;
-    /// let rect = Rect::new(200, 100, 10, 10);
-    /// let buffer = Buffer::empty(rect);
-    /// // Global coordinates to the top corner of this buffer's area
-    /// assert_eq!(buffer.index_of(200, 100), 0);
+    /// # use crossterm_window::buffer::Buffer;
+    /// let buffer = Buffer::empty(10, 10);
+    /// assert_eq!(buffer.index_of(0, 0), 0);
+    /// assert_eq!(buffer.index_of(4, 1), 14);
     /// ```
     ///
     /// # Panics
@@ -149,33 +363,28 @@ impl Buffer {
     /// Panics when given an coordinate that is outside of this Buffer's area.
     ///
     /// ```should_panic
-    /// # use ratatui::prelude::*;
-    /// let rect = Rect::new(200, 100, 10, 10);
-    /// let buffer = Buffer::empty(rect);
-    /// // Top coordinate is outside of the buffer in global coordinate space, as the Buffer's area
-    /// // starts at (200, 100).
-    /// buffer.index_of(0, 0); // Panics
+    /// # use crossterm_window::buffer::Buffer;
+    /// let buffer = Buffer::empty(10, 10);
+    /// // (10, 0) lies just past the right edge of the buffer.
+    /// buffer.index_of(10, 0); // Panics
     /// ```
     pub fn index_of(&self, x: u16, y: u16) -> usize {
         debug_assert!(
             x < self.width && y < self.height,
             "Trying to access position outside the buffer: x={x}, y={y}",
         );
-        (y * self.width + x) as usize
+        y as usize * self.width as usize + x as usize
     }
 
-    /// Returns the (global) coordinates of a cell given its index
-    ///
-    /// Global coordinates are offset by the Buffer's area offset (`x`/`y`).
+    /// Returns the coordinates of a cell given its index
     ///
     /// # Examples
     ///
     /// ```
-    /// # use ratatui::prelude::*;
-    /// let rect = Rect::new(200, 100, 10, 10);
-    /// let buffer = Buffer::empty(rect);
-    /// assert_eq!(buffer.pos_of(0), (200, 100));
-    /// assert_eq!(buffer.pos_of(14), (204, 101));
+    /// # use crossterm_window::buffer::Buffer;
+    /// let buffer = Buffer::empty(10, 10);
+    /// assert_eq!(buffer.pos_of(0), (0, 0));
+    /// assert_eq!(buffer.pos_of(14), (4, 1));
     /// ```
     ///
     /// # Panics
@@ -183,9 +392,8 @@ impl Buffer {
     /// Panics when given an index that is outside the Buffer's content.
     ///
     /// ```should_panic
-    /// # use ratatui::prelude::*;
-    /// let rect = Rect::new(0, 0, 10, 10); // 100 cells in total
-    /// let buffer = Buffer::empty(rect);
+    /// # use crossterm_window::buffer::Buffer;
+    /// let buffer = Buffer::empty(10, 10); // 100 cells in total
     /// // Index 100 is the 101th cell, which lies outside of the area of this Buffer.
     /// buffer.pos_of(100); // Panics
     /// ```
@@ -195,16 +403,116 @@ impl Buffer {
             "Trying to get the coords of a cell outside the buffer: i={i} len={}",
             self.content.len()
         );
-        ((i as u16) % self.width, (i as u16) / self.width)
+        let width = self.width as usize;
+        ((i % width) as u16, (i / width) as u16)
+    }
+
+    /// Returns the cell at (x, y), or `None` if it lies outside the buffer.
+    pub fn get(&self, x: u16, y: u16) -> Option<&Cell> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(&self.content[self.index_of(x, y)])
     }
 
-    pub fn set_lines<S>(&mut self, x: u16, y: u16, string: S, style: Style)
+    /// Returns the cell at (x, y) for writing, or `None` if it lies outside the buffer.
+    pub fn get_mut(&mut self, x: u16, y: u16) -> Option<&mut Cell> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.invalidate_rows(y..y + 1);
+        let i = self.index_of(x, y);
+        Some(&mut self.content[i])
+    }
+
+    pub fn tag_cell(&mut self, x: u16, y: u16, tag: u64) {
+        let i = self.index_of(x, y);
+        self.content[i].set_tag(Some(tag));
+        self.invalidate_rows(y..y + 1);
+    }
+
+    pub fn tag_at(&self, x: u16, y: u16) -> Option<u64> {
+        self.content[self.index_of(x, y)].tag
+    }
+
+    /// Prints each line of a string on its own row, starting at (x, y), and returns the row of
+    /// the last line (`y` if there are none).
+    pub fn set_lines<S>(&mut self, x: u16, y: u16, string: S, style: Style) -> u16
     where
         S: AsRef<str>,
     {
+        let mut last = y;
         for (i, s) in string.as_ref().lines().enumerate() {
-            self.set_string(x, y + i as u16, s, style)
+            last = y + i as u16;
+            self.set_string(x, last, s, style);
         }
+        last
+    }
+
+    /// Print the spans of `line` one after the other, starting at (x, y) and stopping at the end
+    /// of the row. Returns the position after the last grapheme written, like `set_stringn`.
+    pub fn set_line(&mut self, x: u16, y: u16, line: &Line) -> (u16, u16) {
+        let right = self.width;
+        self.set_line_until(x, y, line, right)
+    }
+
+    /// Prints `line` on the top row of `area`, placed according to `alignment`. The rest of the
+    /// row is cleared, and the line is cut off at the right edge if it is wider than the area.
+    pub fn set_line_aligned(&mut self, area: Rect, line: &Line, alignment: Alignment) {
+        let area = area.intersection(Rect::new(0, 0, self.width, self.height));
+        if area.is_empty() {
+            return;
+        }
+        self.fill(Rect::new(area.x, area.y, area.width, 1), &Cell::default());
+        let width = area.width as usize;
+        let len = line.width().min(width);
+        let offset = match alignment {
+            Alignment::Left => 0,
+            Alignment::Center => (width - len) / 2,
+            Alignment::Right => width - len,
+        } as u16;
+        self.set_line_until(area.x + offset, area.y, line, area.right());
+    }
+
+    /// Prints the spans of `line` from (x, y), leaving the columns from `right` on untouched.
+    fn set_line_until(&mut self, x: u16, y: u16, line: &Line, right: u16) -> (u16, u16) {
+        let mut x = x;
+        for span in &line.0 {
+            if x >= right {
+                break;
+            }
+            let width = (right - x) as usize;
+            x = self.set_stringn(x, y, &span.content, width, span.style).0;
+        }
+        (x, y)
+    }
+
+    /// Print a string starting at (x, y), continuing at the start of the next row whenever a
+    /// row is full, and return the position the next write should start at.
+    ///
+    /// Writing stops at the bottom of the buffer.
+    pub fn set_span<S>(&mut self, x: u16, y: u16, string: S, style: Style) -> (u16, u16)
+    where
+        S: AsRef<str>,
+    {
+        let (mut x, mut y) = (x, y);
+        for s in UnicodeSegmentation::graphemes(string.as_ref(), true) {
+            let width = self.grapheme_width(s);
+            if width > self.width as usize {
+                break;
+            }
+            if x as usize + width > self.width as usize {
+                (x, y) = (0, y + 1);
+            }
+            if y >= self.height {
+                break;
+            }
+            x = self.set_stringn(x, y, s, width, style).0;
+        }
+        if x >= self.width {
+            (x, y) = (0, y + 1);
+        }
+        (x, y)
     }
 
     /// Print a string, starting at the position (x, y)
@@ -217,6 +525,12 @@ impl Buffer {
 
     /// Print at most the first n characters of a string if enough space is available
     /// until the end of the line
+    ///
+    /// Returns the column after the last grapheme written along with `y`, which is unchanged as
+    /// the string never wraps. Use `set_span` for text that continues on the following rows.
+    ///
+    /// Every grapheme takes up one cell, followed by blank cells for the rest of its width. Zero
+    /// width joiner sequences are measured according to `zwj_width`.
     pub fn set_stringn<S>(
         &mut self,
         x: u16,
@@ -228,12 +542,14 @@ impl Buffer {
     where
         S: AsRef<str>,
     {
+        self.invalidate_rows(y..y + 1);
         let mut index = self.index_of(x, y);
+        let row_end = self.index_of(0, y) + self.width as usize;
         let mut x_offset = x as usize;
         let graphemes = UnicodeSegmentation::graphemes(string.as_ref(), true);
         let max_offset = min(self.width as usize, width.saturating_add(x as usize));
         for s in graphemes {
-            let width = s.width();
+            let width = self.grapheme_width(s);
             if width == 0 {
                 continue;
             }
@@ -245,8 +561,9 @@ impl Buffer {
 
             self.content[index].set_symbol(s);
             self.content[index].set_style(style);
-            // Reset following cells if multi-width (they would be hidden by the grapheme),
-            for i in index + 1..index + width {
+            // Reset following cells if multi-width (they would be hidden by the grapheme), never
+            // going past the end of the row.
+            for i in index + 1..min(index + width, row_end) {
                 self.content[i].reset();
             }
             index += width;
@@ -255,16 +572,109 @@ impl Buffer {
         (x_offset as u16, y)
     }
 
+    /// Writes `text` into `area`, wrapping lines at whitespace where possible and breaking words
+    /// wider than the area. Lines that don't fit below the area are dropped.
+    pub fn set_string_wrapped(&mut self, area: Rect, text: &str, style: Style) {
+        let area = area.intersection(Rect::new(0, 0, self.width, self.height));
+        let width = area.width as usize;
+        for (y, line) in self.wrap(text, width).into_iter().enumerate() {
+            if y >= area.height as usize {
+                break;
+            }
+            self.set_stringn(area.x, area.y + y as u16, line, width, style);
+        }
+    }
+
+    /// Splits `text` into lines of at most `width` columns, breaking on whitespace where possible.
+    fn wrap(&self, text: &str, width: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        if width == 0 {
+            return lines;
+        }
+
+        for paragraph in text.lines() {
+            let mut line = String::new();
+            let mut line_width = 0;
+            for word in paragraph.split_whitespace() {
+                let word_width: usize = word.graphemes(true).map(|g| self.grapheme_width(g)).sum();
+                if line_width > 0 && line_width + 1 + word_width <= width {
+                    line.push(' ');
+                    line.push_str(word);
+                    line_width += 1 + word_width;
+                    continue;
+                }
+                if line_width > 0 {
+                    lines.push(std::mem::take(&mut line));
+                    line_width = 0;
+                }
+                // Words wider than a line are broken up.
+                for g in word.graphemes(true) {
+                    let g_width = self.grapheme_width(g);
+                    if line_width + g_width > width {
+                        lines.push(std::mem::take(&mut line));
+                        line_width = 0;
+                    }
+                    line.push_str(g);
+                    line_width += g_width;
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    fn grapheme_width(&self, grapheme: &str) -> usize {
+        match self.zwj_width {
+            ZwjWidth::Split if grapheme.contains('\u{200D}') => {
+                grapheme.split('\u{200D}').map(|part| part.width()).sum()
+            }
+            _ => grapheme.width(),
+        }
+    }
+
+    /// Applies `style` to every cell in `area` with `Cell::set_style`, ignoring the part of it
+    /// outside the buffer. The cells are walked as one slice of the content per row rather than
+    /// looked up by position.
     pub fn set_style(&mut self, area: Rect, style: Style) {
-        for x in area.x..area.width+area.x {
-            for y in area.y..area.height+area.y {
-                let i = self.index_of(x, y);
-                self.content[i].set_style(style);
+        let left = area.left().min(self.width) as usize;
+        let right = area.right().min(self.width) as usize;
+        let width = self.width as usize;
+        self.invalidate_rows(area.top()..area.bottom());
+        for y in area.top() as usize..min(area.bottom(), self.height) as usize {
+            for cell in &mut self.content[y * width + left..y * width + right] {
+                cell.set_style(style);
             }
         }
     }
 
+    /// Sets the `skip` flag of every cell in `area`, ignoring the part of it outside the buffer,
+    /// e.g. to keep the region of an image drawn by other means from being overwritten.
+    pub fn set_skip(&mut self, area: Rect, skip: bool) {
+        let area = area.intersection(Rect::new(0, 0, self.width, self.height));
+        let width = self.width as usize;
+        self.invalidate_rows(area.top()..area.bottom());
+        for y in area.top() as usize..area.bottom() as usize {
+            let row = y * width;
+            for cell in &mut self.content[row + area.left() as usize..row + area.right() as usize] {
+                cell.skip = skip;
+            }
+        }
+    }
+
+    /// Sets every cell in `area` to a clone of `cell`, ignoring the part of it outside the buffer.
+    pub fn fill(&mut self, area: Rect, cell: &Cell) {
+        let area = area.intersection(Rect::new(0, 0, self.width, self.height));
+        let width = self.width as usize;
+        self.invalidate_rows(area.top()..area.bottom());
+        for y in area.top() as usize..area.bottom() as usize {
+            let row = y * width;
+            self.content[row + area.left() as usize..row + area.right() as usize]
+                .fill(cell.clone());
+        }
+    }
+
     pub fn insert(&mut self, x: u16, y: u16, other: &Self) {
+        self.invalidate_rows(y..y.saturating_add(other.height));
         for (i, cell) in other.content.iter().enumerate() {
             let (xc, yc) = other.pos_of(i);
             let index = self.index_of(x + xc, y + yc);
@@ -272,19 +682,262 @@ impl Buffer {
         }
     }
 
-    pub fn draw(&self) -> BufferDrawIterator {
+    /// Like `insert`, but instead of panicking when `other` doesn't fit, only copies the part of
+    /// it that lands inside this buffer. Returns the area that was written, which is smaller
+    /// than `other` if anything was clipped.
+    pub fn try_insert(&mut self, x: u16, y: u16, other: &Self) -> Rect {
+        let written = Rect::new(x, y, other.width, other.height).intersection(Rect::new(
+            0,
+            0,
+            self.width,
+            self.height,
+        ));
+        self.blit((x, y), other, Rect::new(0, 0, other.width, other.height));
+        written
+    }
+
+    /// Copies the part of `src` inside `src_area` to `dest` in this buffer. `src_area` is
+    /// clamped to `src`, and whatever would land outside of this buffer is left out.
+    pub fn blit(&mut self, dest: (u16, u16), src: &Buffer, src_area: Rect) {
+        let src_area = src_area.intersection(Rect::new(0, 0, src.width, src.height));
+        let width = src_area.width.min(self.width.saturating_sub(dest.0));
+        let height = src_area.height.min(self.height.saturating_sub(dest.1));
+        if width == 0 {
+            return;
+        }
+        self.invalidate_rows(dest.1..dest.1 + height);
+        for dy in 0..height {
+            let from = src.index_of(src_area.x, src_area.y + dy);
+            let to = self.index_of(dest.0, dest.1 + dy);
+            self.content[to..to + width as usize]
+                .clone_from_slice(&src.content[from..from + width as usize]);
+        }
+    }
+
+    /// Like `insert`, but leaves destination cells untouched where the source cell has `skip`
+    /// set, so regions owned by something else (e.g. an image) are not overwritten.
+    pub fn merge(&mut self, x: u16, y: u16, other: &Self) {
+        self.invalidate_rows(y..y.saturating_add(other.height));
+        for (i, cell) in other.content.iter().enumerate() {
+            if cell.skip {
+                continue;
+            }
+            let (xc, yc) = other.pos_of(i);
+            let index = self.index_of(x + xc, y + yc);
+            self.content[index] = cell.clone();
+        }
+    }
+
+    /// Like `insert`, but treats blank source cells (a space without colors or modifiers) as
+    /// transparent, leaving the destination visible through them. This allows layering windows
+    /// that aren't rectangular.
+    pub fn insert_transparent(&mut self, x: u16, y: u16, other: &Self) {
+        self.invalidate_rows(y..y.saturating_add(other.height));
+        for (i, cell) in other.content.iter().enumerate() {
+            let blank = cell.symbol == " "
+                && cell.fg == Color::Reset
+                && cell.bg == Color::Reset
+                && cell.modifier.is_empty();
+            if blank {
+                continue;
+            }
+            let (xc, yc) = other.pos_of(i);
+            let index = self.index_of(x + xc, y + yc);
+            self.content[index] = cell.clone();
+        }
+    }
+
+    /// Blends two buffers of the same size, `t = 0.0` giving `a` and `t = 1.0` giving `b`.
+    ///
+    /// Colors are blended with `text::blend`, while the symbol and modifiers switch from `a` to
+    /// `b` at `t = 0.5`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffers differ in size.
+    pub fn crossfade(a: &Buffer, b: &Buffer, t: f32) -> Buffer {
+        assert!(
+            a.width == b.width && a.height == b.height,
+            "Trying to crossfade buffers of different sizes: {}x{} and {}x{}",
+            a.width,
+            a.height,
+            b.width,
+            b.height,
+        );
+
+        let mut out = if t < 0.5 { a.clone() } else { b.clone() };
+        for (i, cell) in out.content.iter_mut().enumerate() {
+            cell.fg = text::blend(a.content[i].fg, b.content[i].fg, t);
+            cell.bg = text::blend(a.content[i].bg, b.content[i].bg, t);
+        }
+        out.row_hashes.0.clear();
+        out
+    }
+
+    /// Iterates over the rows of cells, from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.content.chunks(self.width.max(1) as usize)
+    }
+
+    /// Iterates over the rows of cells for writing, from top to bottom.
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [Cell]> {
+        self.row_hashes.0.clear();
+        self.content.chunks_mut(self.width.max(1) as usize)
+    }
+
+    /// Iterates over every cell with its coordinates, row by row. Unlike `draw`, cells with
+    /// `skip` set are included.
+    pub fn cells(&self) -> impl Iterator<Item = (u16, u16, &Cell)> {
+        let width = self.width as usize;
+        self.content
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width) as u16, (i / width) as u16, cell))
+    }
+
+    pub fn draw(&self) -> BufferDrawIterator<'_> {
         BufferDrawIterator {
             buffer: self,
             index: 0,
         }
     }
 
-    pub fn diff<'a>(&'a self, other: &'a Self) -> BufferDiffIterator {
+    /// Iterates over the cells of `other` that differ from the same cell in `self`.
+    ///
+    /// Both buffers must have the same size, otherwise there is no meaningful way to pair their
+    /// cells and nothing is yielded.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> BufferDiffIterator<'a> {
+        let same_size = self.width == other.width && self.height == other.height;
         BufferDiffIterator {
             buffer_one: self,
             buffer_two: other,
-            index: 0,
+            index: if same_size { 0 } else { self.area() },
+            covered: 0,
+            #[cfg(test)]
+            compared: 0,
+        }
+    }
+
+    /// Compares only the size and the symbols of the cells, ignoring their styles, e.g. to check
+    /// where text ended up regardless of color.
+    pub fn content_eq(&self, other: &Buffer) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self
+                .content
+                .iter()
+                .zip(&other.content)
+                .all(|(a, b)| a.symbol == b.symbol)
+    }
+
+    /// Returns a copy of the buffer without the rows and columns at the end in which every cell
+    /// is `Cell::default()`, e.g. to keep snapshots small. A blank buffer trims down to 0x0.
+    pub fn trim_end(&self) -> Buffer {
+        let blank = Cell::default();
+        let (mut width, mut height) = (0, 0);
+        for (i, cell) in self.content.iter().enumerate() {
+            if *cell == blank {
+                continue;
+            }
+            let (x, y) = self.pos_of(i);
+            // Keep the cells covered by a wide grapheme along with it.
+            let cell_width = self.grapheme_width(&cell.symbol).max(1) as u16;
+            width = width.max(x.saturating_add(cell_width).min(self.width));
+            height = y + 1;
         }
+
+        let mut trimmed = Buffer::empty(width, height);
+        trimmed.zwj_width = self.zwj_width;
+        trimmed.fill_cell = self.fill_cell.clone();
+        trimmed.blit((0, 0), self, Rect::new(0, 0, width, height));
+        trimmed
+    }
+
+    /// Returns the symbols of each row as a string, without any styling. Cells hidden behind a
+    /// wide character are left out, so each line reads as it shows on screen.
+    pub fn to_lines(&self) -> Vec<String> {
+        if self.width == 0 {
+            return Vec::new();
+        }
+        self.content
+            .chunks(self.width as usize)
+            .map(|row| {
+                let mut line = String::new();
+                let mut hidden = 0;
+                for cell in row {
+                    if hidden > 0 {
+                        hidden -= 1;
+                        continue;
+                    }
+                    line.push_str(&cell.symbol);
+                    hidden = self.grapheme_width(&cell.symbol).saturating_sub(1);
+                }
+                line
+            })
+            .collect()
+    }
+
+    /// Returns the buffer as text like `to_lines`, with a marker such as `[fg=Red]` wherever the
+    /// colors or modifiers change, for debugging rendering issues. Every row starts out with
+    /// the default style.
+    pub fn debug_styled(&self) -> String {
+        if self.width == 0 {
+            return String::new();
+        }
+        let mut lines = Vec::new();
+        for row in self.content.chunks(self.width as usize) {
+            let mut line = String::new();
+            let mut current = &Cell::default();
+            let mut hidden = 0;
+            for cell in row {
+                if hidden > 0 {
+                    hidden -= 1;
+                    continue;
+                }
+                let mut changes = Vec::new();
+                if cell.fg != current.fg {
+                    changes.push(format!("fg={:?}", cell.fg));
+                }
+                if cell.bg != current.bg {
+                    changes.push(format!("bg={:?}", cell.bg));
+                }
+                if cell.modifier != current.modifier {
+                    changes.push(format!("mod={:?}", cell.modifier));
+                }
+                if !changes.is_empty() {
+                    line.push_str(&format!("[{}]", changes.join(" ")));
+                }
+                line.push_str(&cell.symbol);
+                current = cell;
+                hidden = self.grapheme_width(&cell.symbol).saturating_sub(1);
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
+
+/// Accesses the cell at `(x, y)`, panicking outside the buffer like `index_of`.
+impl Index<(u16, u16)> for Buffer {
+    type Output = Cell;
+
+    fn index(&self, (x, y): (u16, u16)) -> &Cell {
+        &self.content[self.index_of(x, y)]
+    }
+}
+
+impl IndexMut<(u16, u16)> for Buffer {
+    fn index_mut(&mut self, (x, y): (u16, u16)) -> &mut Cell {
+        let i = self.index_of(x, y);
+        self.invalidate_rows(y..y + 1);
+        &mut self.content[i]
+    }
+}
+
+/// Shows the buffer as plain text, see `Buffer::to_lines`.
+impl fmt::Display for Buffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_lines().join("\n"))
     }
 }
 
@@ -292,6 +945,11 @@ pub struct BufferDiffIterator<'a> {
     buffer_one: &'a Buffer,
     buffer_two: &'a Buffer,
     index: usize,
+    /// Number of cells after the last one looked at that are hidden behind its wide grapheme.
+    covered: usize,
+    /// Number of cells compared so far.
+    #[cfg(test)]
+    compared: usize,
 }
 
 impl<'a> Iterator for BufferDiffIterator<'a> {
@@ -299,15 +957,43 @@ impl<'a> Iterator for BufferDiffIterator<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if self.index + 1 > self.buffer_one.area() as usize {
+            if self.index >= self.buffer_one.area() {
                 return None;
             }
 
-            if self.buffer_one.content[self.index] != self.buffer_two.content[self.index] {
+            let width = self.buffer_one.width as usize;
+            if self.index.is_multiple_of(width) {
+                self.covered = 0;
+                let y = self.index / width;
+                let one = self.buffer_one.row_hashes.get(y);
+                if one.is_some() && one == self.buffer_two.row_hashes.get(y) {
+                    self.index += width;
+                    continue;
+                }
+            }
+
+            // Writing the wide grapheme before a covered cell already drew over it, so yielding the
+            // cell would erase half of the grapheme again.
+            if self.covered > 0 {
+                self.covered -= 1;
+                self.index += 1;
+                continue;
+            }
+            let cell = &self.buffer_two.content[self.index];
+            self.covered = self
+                .buffer_two
+                .grapheme_width(&cell.symbol)
+                .saturating_sub(1);
+
+            #[cfg(test)]
+            {
+                self.compared += 1;
+            }
+            if self.buffer_one.content[self.index] != *cell {
                 let out = Some((
-                    self.index as u16 % self.buffer_two.width,
-                    self.index as u16 / self.buffer_two.width,
-                    &self.buffer_two.content[self.index],
+                    (self.index % self.buffer_two.width as usize) as u16,
+                    (self.index / self.buffer_two.width as usize) as u16,
+                    cell,
                 ));
 
                 self.index += 1;
@@ -329,7 +1015,7 @@ impl<'a> Iterator for BufferDrawIterator<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if self.index + 1 > self.buffer.area() as usize {
+            if self.index >= self.buffer.area() {
                 return None;
             }
 
@@ -339,8 +1025,8 @@ impl<'a> Iterator for BufferDrawIterator<'a> {
             }
 
             let out = Some((
-                self.index as u16 % self.buffer.width,
-                self.index as u16 / self.buffer.width,
+                (self.index % self.buffer.width as usize) as u16,
+                (self.index / self.buffer.width as usize) as u16,
                 &self.buffer.content[self.index],
             ));
 
@@ -349,3 +1035,582 @@ impl<'a> Iterator for BufferDrawIterator<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draw_iterates_large_buffers() {
+        let buffer = Buffer {
+            width: 400,
+            height: 200,
+            content: vec![Cell::default(); 80_000],
+            ..Buffer::default()
+        };
+        assert_eq!(buffer.draw().count(), 80_000);
+        assert_eq!(
+            buffer.draw().last().map(|(x, y, _)| (x, y)),
+            Some((399, 199))
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let mut buffer = Buffer::empty(5, 2);
+        buffer.tag_cell(1, 0, 7);
+        buffer.tag_cell(3, 1, 42);
+        assert_eq!(buffer.tag_at(1, 0), Some(7));
+        assert_eq!(buffer.tag_at(3, 1), Some(42));
+        assert_eq!(buffer.tag_at(0, 0), None);
+
+        buffer.set_string(0, 0, "abc", Style::default().fg(Color::Red));
+        assert_eq!(buffer.tag_at(1, 0), Some(7));
+
+        buffer.reset();
+        assert_eq!(buffer.tag_at(1, 0), None);
+        assert_eq!(buffer.tag_at(3, 1), None);
+    }
+
+    #[test]
+    fn zwj_sequence() {
+        let family = "👨\u{200D}👩\u{200D}👧";
+
+        let mut buffer = Buffer::empty(10, 1);
+        let (x, _) = buffer.set_stringn(0, 0, format!("{family}x"), usize::MAX, Style::default());
+        assert_eq!(x, 3);
+        assert_eq!(buffer.content[0].symbol, family);
+        assert_eq!(buffer.content[1].symbol, " ");
+        assert_eq!(buffer.content[2].symbol, "x");
+
+        let mut buffer = Buffer::empty(10, 1);
+        buffer.zwj_width = ZwjWidth::Split;
+        let (x, _) = buffer.set_stringn(0, 0, format!("{family}x"), usize::MAX, Style::default());
+        assert_eq!(x, 7);
+        assert_eq!(buffer.content[0].symbol, family);
+        assert!(buffer.content[1..6].iter().all(|c| c.symbol == " "));
+        assert_eq!(buffer.content[6].symbol, "x");
+    }
+
+    #[test]
+    fn crossfade() {
+        let mut a = Buffer::empty(4, 2);
+        a.set_string(0, 0, "aaaa", Style::default().fg(Color::Black));
+        a.set_style(Rect::new(0, 1, 4, 1), Style::default().bg(Color::Blue));
+        let mut b = Buffer::empty(4, 2);
+        b.set_string(0, 0, "bb", Style::default().fg(Color::White));
+
+        assert_eq!(Buffer::crossfade(&a, &b, 0.0), a);
+        assert_eq!(Buffer::crossfade(&a, &b, 1.0), b);
+
+        let half = Buffer::crossfade(&a, &b, 0.5);
+        assert_eq!(half.content[0].symbol, "b");
+        let gray = text::blend(Color::Black, Color::White, 0.5);
+        assert_eq!(half.content[0].fg, gray);
+        assert_eq!(half.content[4].bg, Color::Reset);
+    }
+
+    fn rows(buffer: &Buffer) -> Vec<String> {
+        buffer
+            .content
+            .chunks(buffer.width as usize)
+            .map(|row| row.iter().map(|c| c.symbol.as_str()).collect())
+            .collect()
+    }
+
+    fn numbered(height: u16) -> Buffer {
+        let mut buffer = Buffer::empty(3, height);
+        for y in 0..height {
+            buffer.set_string(0, y, format!("r{y}"), Style::default());
+        }
+        buffer
+    }
+
+    #[test]
+    fn scroll_up() {
+        let mut buffer = numbered(5);
+        buffer.scroll_up(2);
+        assert_eq!(rows(&buffer), ["r2 ", "r3 ", "r4 ", "   ", "   "]);
+
+        buffer.scroll_up(5);
+        assert_eq!(buffer, Buffer::empty(3, 5));
+    }
+
+    #[test]
+    fn scroll_down() {
+        let mut buffer = numbered(5);
+        buffer.scroll_down(2);
+        assert_eq!(rows(&buffer), ["   ", "   ", "r0 ", "r1 ", "r2 "]);
+
+        buffer.scroll_down(7);
+        assert_eq!(buffer, Buffer::empty(3, 5));
+    }
+
+    #[test]
+    fn set_style_clips_to_buffer() {
+        let mut buffer = Buffer::empty(5, 4);
+        let style = Style::default().bg(Color::Red);
+        buffer.set_style(Rect::new(3, 2, 10, 10), style);
+
+        for (i, cell) in buffer.content.iter().enumerate() {
+            let (x, y) = buffer.pos_of(i);
+            let expected = if x >= 3 && y >= 2 {
+                Color::Red
+            } else {
+                Color::Reset
+            };
+            assert_eq!(cell.bg, expected, "cell at ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn merge_respects_skip() {
+        let mut dest = Buffer::empty(5, 1);
+        dest.set_string(0, 0, "image", Style::default());
+
+        let mut src = Buffer::empty(3, 1);
+        src.set_string(0, 0, "abc", Style::default());
+        src.content[1].set_skip(true);
+
+        dest.merge(1, 0, &src);
+        assert_eq!(rows(&dest), vec!["iaace"]);
+    }
+
+    #[test]
+    fn diff_of_different_sizes_is_empty() {
+        let a = Buffer::empty(10, 10);
+        let mut b = Buffer::empty(8, 8);
+        b.set_string(0, 0, "changed", Style::default());
+
+        assert_eq!(a.diff(&b).count(), 0);
+        assert_eq!(b.diff(&a).count(), 0);
+    }
+
+    #[test]
+    fn diff_skips_cells_behind_wide_graphemes() {
+        let mut a = Buffer::empty(6, 1);
+        a.set_string(0, 0, "abcd", Style::default());
+        let mut b = a.clone();
+        b.set_string(0, 0, "世", Style::default());
+
+        let changed: Vec<_> = a
+            .diff(&b)
+            .map(|(x, y, cell)| (x, y, cell.symbol.as_str()))
+            .collect();
+        assert_eq!(changed, vec![(0, 0, "世")]);
+
+        // Going back to narrow graphemes writes both cells again.
+        let changed: Vec<_> = b.diff(&a).map(|(x, _, _)| x).collect();
+        assert_eq!(changed, vec![0, 1]);
+    }
+
+    #[test]
+    fn set_style_matches_cell_set_style() {
+        let style = Style::default()
+            .fg(Color::Red)
+            .add_modifier(Modifier::BOLD)
+            .remove_modifier(Modifier::ITALIC);
+        let mut buffer = Buffer::empty(80, 24);
+        for (i, cell) in buffer.content.iter_mut().enumerate() {
+            cell.set_bg(Color::AnsiValue(i as u8));
+            if i % 3 == 0 {
+                cell.modifier = Modifier::ITALIC | Modifier::UNDERLINED;
+            }
+        }
+
+        let mut expected = buffer.clone();
+        for cell in &mut expected.content {
+            cell.set_style(style);
+        }
+        buffer.set_style(Rect::new(0, 0, 80, 24), style);
+        assert_eq!(buffer, expected);
+    }
+
+    #[test]
+    fn wrap_breaks_long_words() {
+        let buffer = Buffer::empty(1, 1);
+        assert_eq!(buffer.wrap("a abcdefgh b", 4), ["a", "abcd", "efgh", "b"]);
+        assert_eq!(buffer.wrap("one\n\ntwo", 10), ["one", "", "two"]);
+    }
+
+    #[test]
+    fn set_string_wrapped() {
+        let mut buffer = Buffer::empty(12, 5);
+        let area = Rect::new(1, 1, 10, 3);
+        buffer.set_string_wrapped(area, "the quick brown fox jumps over it", Style::default());
+        assert_eq!(
+            rows(&buffer),
+            [
+                "            ",
+                " the quick  ",
+                " brown fox  ",
+                " jumps over ",
+                "            ",
+            ]
+        );
+    }
+
+    #[test]
+    fn set_string_wrapped_breaks_wide_words() {
+        let mut buffer = Buffer::empty(4, 3);
+        buffer.set_string_wrapped(Rect::new(0, 0, 4, 3), "abcdefghijklmn", Style::default());
+        assert_eq!(rows(&buffer), ["abcd", "efgh", "ijkl"]);
+
+        let mut buffer = Buffer::empty(3, 2);
+        buffer.set_string_wrapped(Rect::new(0, 0, 3, 2), "日本語", Style::default());
+        assert_eq!(rows(&buffer), ["日  ", "本  "]);
+    }
+
+    #[test]
+    fn wide_char_at_row_end() {
+        let mut buffer = Buffer::empty(4, 2);
+        buffer.set_string(0, 1, "next", Style::default());
+
+        buffer.set_string(2, 0, "日", Style::default());
+        assert_eq!(rows(&buffer), ["  日 ", "next"]);
+
+        buffer.set_string(3, 0, "本", Style::default());
+        assert_eq!(rows(&buffer), ["  日 ", "next"]);
+    }
+
+    #[test]
+    fn to_lines() {
+        let mut buffer = Buffer::empty(8, 2);
+        buffer.set_string(0, 0, "hello", Style::default().fg(Color::Red));
+        buffer.set_string(0, 1, "日本", Style::default());
+
+        let lines = buffer.to_lines();
+        assert!(lines[0].starts_with("hello"));
+        assert_eq!(lines, ["hello   ", "日本    "]);
+        assert_eq!(buffer.to_string(), "hello   \n日本    ");
+    }
+
+    #[test]
+    fn debug_styled() {
+        let mut buffer = Buffer::empty(6, 2);
+        buffer.set_string(0, 0, "red", Style::default().fg(Color::Red));
+        buffer.set_string(3, 0, "def", Style::default());
+        buffer.set_string(
+            1,
+            1,
+            "b",
+            Style::default()
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        assert_eq!(
+            buffer.debug_styled(),
+            "[fg=Red]red[fg=Reset]def\n [bg=Blue mod=BOLD]b[bg=Reset mod=NONE]    "
+        );
+    }
+
+    #[test]
+    fn fill() {
+        let mut buffer = Buffer::empty(10, 10);
+        let mut block = Cell::default();
+        block.set_symbol("█");
+        buffer.fill(Rect::new(2, 3, 3, 3), &block);
+
+        for y in 0..10 {
+            for x in 0..10 {
+                let inside = (2..5).contains(&x) && (3..6).contains(&y);
+                let expected = if inside { &block } else { &Cell::default() };
+                assert_eq!(
+                    &buffer.content[buffer.index_of(x, y)],
+                    expected,
+                    "({x}, {y})"
+                );
+            }
+        }
+
+        buffer.fill(Rect::new(8, 8, 5, 5), &block);
+        assert_eq!(buffer.to_lines()[9], "        ██");
+    }
+
+    #[test]
+    fn diff_skips_rows_with_matching_hashes() {
+        let mut a = Buffer::empty(10, 5);
+        a.set_lines(0, 0, "one\ntwo\nthree", Style::default());
+        let mut b = a.clone();
+        b.set_string(1, 2, "X", Style::default());
+        a.hash_rows();
+        b.hash_rows();
+
+        let mut diff = a.diff(&b);
+        let changed: Vec<_> = diff.by_ref().map(|(x, y, _)| (x, y)).collect();
+        assert_eq!(changed, vec![(1, 2)]);
+        assert_eq!(diff.compared, 10);
+
+        // Rows written after hashing are compared cell by cell again.
+        b.set_string(0, 4, "Y", Style::default());
+        let mut diff = a.diff(&b);
+        assert_eq!(diff.by_ref().count(), 2);
+        assert_eq!(diff.compared, 20);
+    }
+
+    #[test]
+    fn insert_transparent() {
+        let mut buffer = Buffer::empty(6, 3);
+        buffer.set_lines(0, 0, "abcdef\nghijkl\nmnopqr", Style::default());
+        let mut other = Buffer::empty(3, 2);
+        other.set_string(1, 1, "X", Style::default());
+
+        let mut expected = buffer.clone();
+        expected.set_string(3, 2, "X", Style::default());
+        buffer.insert_transparent(2, 1, &other);
+        assert_eq!(buffer, expected);
+    }
+
+    #[test]
+    fn index_by_position() {
+        let mut buffer = Buffer::empty(5, 5);
+        buffer[(2, 3)].set_symbol("x").set_fg(Color::Red);
+
+        let i = buffer.index_of(2, 3);
+        assert_eq!(buffer[(2, 3)], buffer.content[i]);
+        assert_eq!(buffer[(2, 3)].symbol, "x");
+        assert_eq!(buffer.get(2, 3), Some(&buffer.content[i]));
+        assert_eq!(buffer.get(5, 0), None);
+        assert!(buffer.get_mut(0, 5).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_outside_panics() {
+        let buffer = Buffer::empty(5, 5);
+        let _ = &buffer[(0, 5)];
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mut buffer = Buffer::empty(4, 2);
+        buffer.set_string(0, 0, "hi", Style::default().fg(Color::Red));
+        buffer.set_style(
+            Rect::new(1, 1, 2, 1),
+            Style::default().add_modifier(Modifier::BOLD),
+        );
+        buffer[(3, 1)]
+            .set_hyperlink("https://example.com")
+            .set_tag(Some(7));
+
+        let json = serde_json::to_string(&buffer).unwrap();
+        assert_eq!(serde_json::from_str::<Buffer>(&json).unwrap(), buffer);
+    }
+
+    #[test]
+    fn content_eq_ignores_style() {
+        let mut a = Buffer::empty(5, 2);
+        let mut b = Buffer::empty(5, 2);
+        a.set_string(1, 1, "abc", Style::default().fg(Color::Red));
+        b.set_string(1, 1, "abc", Style::default().fg(Color::Blue));
+        assert!(a.content_eq(&b));
+        assert_ne!(a, b);
+
+        b.set_string(0, 0, "x", Style::default());
+        assert!(!a.content_eq(&b));
+        assert!(!a.content_eq(&Buffer::empty(2, 5)));
+    }
+
+    #[test]
+    fn blit_clips_to_both_buffers() {
+        let mut src = Buffer::empty(4, 4);
+        src.set_lines(0, 0, "abcd\nefgh\nijkl\nmnop", Style::default());
+        src[(3, 3)].set_fg(Color::Red);
+
+        let mut dest = Buffer::empty(3, 3);
+        dest.blit((0, 0), &src, Rect::new(2, 2, 2, 2));
+        assert_eq!(dest.to_lines(), vec!["kl ", "op ", "   "]);
+        assert_eq!(dest[(1, 1)], src[(3, 3)]);
+
+        dest.blit((2, 1), &src, Rect::new(0, 0, 10, 10));
+        assert_eq!(dest.to_lines(), vec!["kl ", "opa", "  e"]);
+        dest.blit((3, 0), &src, Rect::new(0, 0, 4, 4));
+        dest.blit((0, 0), &src, Rect::new(4, 0, 4, 4));
+        assert_eq!(dest.to_lines(), vec!["kl ", "opa", "  e"]);
+    }
+
+    #[test]
+    fn rows_and_cells() {
+        let mut buffer = Buffer::empty(3, 2);
+        buffer.set_lines(0, 0, "abc\ndef", Style::default());
+        buffer[(1, 1)].set_skip(true);
+
+        assert_eq!(buffer.rows().count(), 2);
+        assert_eq!(buffer.rows().nth(1).unwrap()[2].symbol, "f");
+
+        let cells: Vec<_> = buffer
+            .cells()
+            .map(|(x, y, cell)| (x, y, cell.symbol.as_str()))
+            .collect();
+        assert_eq!(cells.len(), buffer.area());
+        assert_eq!(
+            cells[..4],
+            [(0, 0, "a"), (1, 0, "b"), (2, 0, "c"), (0, 1, "d")]
+        );
+        assert_eq!(cells[4], (1, 1, "e"));
+
+        for row in buffer.rows_mut() {
+            row[0].set_symbol("x");
+        }
+        assert_eq!(buffer.to_lines(), vec!["xbc", "xef"]);
+    }
+
+    #[test]
+    fn large_buffers() {
+        let mut cell = Cell::default();
+        cell.set_symbol("x");
+        let mut buffer = Buffer::filled(400, 200, &cell);
+        assert_eq!(buffer.content.len(), 80_000);
+        assert_eq!(buffer[(399, 199)], cell);
+
+        buffer.resize(500, 300);
+        assert_eq!(buffer.content.len(), 150_000);
+        buffer.set_string(0, 299, "end", Style::default());
+    }
+
+    #[test]
+    fn set_lines_returns_last_row() {
+        let mut buffer = Buffer::empty(6, 6);
+        let last = buffer.set_lines(1, 2, "one\ntwo\nsix", Style::default());
+        assert_eq!(last, 4);
+        assert_eq!(buffer.to_lines()[4], " six  ");
+        assert_eq!(buffer.set_lines(0, 1, "", Style::default()), 1);
+    }
+
+    #[test]
+    fn set_span_wraps_and_returns_next_position() {
+        let mut buffer = Buffer::empty(4, 3);
+        assert_eq!(buffer.set_span(1, 0, "abcde", Style::default()), (2, 1));
+        assert_eq!(buffer.set_span(2, 1, "fg", Style::default()), (0, 2));
+        // Wide graphemes that don't fit at the end of a row move to the next one.
+        assert_eq!(buffer.set_span(3, 2, "\u{4e16}", Style::default()), (0, 3));
+        assert_eq!(buffer.to_lines(), vec![" abc", "defg", "    "]);
+    }
+
+    #[test]
+    fn from_lines_sizes_to_content() {
+        let buffer = Buffer::from_lines(&["abc", "de"], Style::default());
+        assert_eq!((buffer.width, buffer.height), (3, 2));
+        assert_eq!(buffer.to_lines(), vec!["abc", "de "]);
+
+        let style = Style::default().fg(Color::Red);
+        let buffer = Buffer::from_lines(&["\u{4e16}\u{754c}", "x"], style);
+        assert_eq!((buffer.width, buffer.height), (4, 2));
+        assert_eq!(buffer[(3, 1)].fg, Color::Red);
+
+        let buffer = Buffer::from_lines(&[], Style::default());
+        assert_eq!((buffer.width, buffer.height), (0, 0));
+    }
+
+    #[test]
+    fn reset_uses_fill_cell() {
+        let mut buffer = Buffer::empty(4, 2);
+        buffer.set_string(0, 0, "abcd", Style::default().fg(Color::Red));
+        let mut backdrop = Cell::default();
+        backdrop.set_symbol("·").set_bg(Color::DarkBlue);
+        buffer.fill_cell = backdrop.clone();
+
+        buffer.reset();
+        assert!(buffer.content.iter().all(|cell| *cell == backdrop));
+
+        buffer.resize(5, 3);
+        assert!(buffer.content[8..].iter().all(|cell| *cell == backdrop));
+
+        buffer.set_string(0, 1, "abcde", Style::default());
+        buffer.scroll_up(1);
+        assert_eq!(buffer.content[0].symbol, "a");
+        assert!(buffer.content[10..].iter().all(|cell| *cell == backdrop));
+        buffer.scroll_down(2);
+        assert!(buffer.content[..10].iter().all(|cell| *cell == backdrop));
+        assert_eq!(buffer.content[10].symbol, "a");
+        assert_eq!(Buffer::empty(2, 1).fill_cell, Cell::default());
+    }
+
+    #[test]
+    fn try_insert_clips_to_bounds() {
+        let mut buffer = Buffer::empty(6, 3);
+        let other = Buffer::from_lines(&["abcd", "efgh"], Style::default());
+
+        assert_eq!(buffer.try_insert(4, 2, &other), Rect::new(4, 2, 2, 1));
+        assert_eq!(buffer.to_lines(), vec!["      ", "      ", "    ab"]);
+
+        assert_eq!(buffer.try_insert(1, 0, &other), Rect::new(1, 0, 4, 2));
+        assert!(buffer.try_insert(6, 0, &other).is_empty());
+    }
+
+    #[test]
+    fn trim_end_drops_blank_rows_and_columns() {
+        let mut buffer = Buffer::empty(10, 5);
+        buffer.set_string(0, 0, "hi", Style::default());
+        let trimmed = buffer.trim_end();
+        assert_eq!((trimmed.width, trimmed.height), (2, 1));
+        assert_eq!(trimmed.to_lines(), vec!["hi"]);
+
+        buffer.set_string(3, 2, "\u{4e16}", Style::default());
+        buffer.set_style(Rect::new(0, 3, 1, 1), Style::default().bg(Color::Red));
+        let trimmed = buffer.trim_end();
+        assert_eq!((trimmed.width, trimmed.height), (5, 4));
+
+        let trimmed = Buffer::empty(10, 5).trim_end();
+        assert_eq!((trimmed.width, trimmed.height), (0, 0));
+    }
+
+    #[test]
+    fn set_line_writes_spans_in_sequence() {
+        use crate::text::Span;
+
+        let mut buffer = Buffer::empty(10, 1);
+        let line = Line(vec![
+            Span::styled("OK", Style::default().fg(Color::Red)),
+            Span::raw(" done"),
+        ]);
+        assert_eq!(buffer.set_line(0, 0, &line), (7, 0));
+        assert_eq!(buffer.to_lines(), vec!["OK done   "]);
+        assert_eq!(buffer[(0, 0)].fg, Color::Red);
+        assert_eq!(buffer[(1, 0)].fg, Color::Red);
+        assert!((2..7).all(|x| buffer[(x, 0)].fg == Color::Reset));
+
+        // Spans past the end of the row are cut off.
+        let line = Line::from(vec![Span::raw("\u{4e16}\u{754c}"), Span::raw("abcdefgh")]);
+        assert_eq!(buffer.set_line(0, 0, &line), (10, 0));
+        assert_eq!(buffer.to_lines(), vec!["\u{4e16}\u{754c}abcdef"]);
+    }
+
+    #[test]
+    fn set_line_aligned_positions_line() {
+        let line = Line::from("abcd");
+        let start = |alignment| {
+            let mut buffer = Buffer::filled(12, 1, Cell::default().set_symbol("x"));
+            buffer.set_line_aligned(Rect::new(0, 0, 10, 1), &line, alignment);
+            assert_eq!(&buffer.to_lines()[0][10..], "xx");
+            buffer.to_lines()[0].find('a')
+        };
+        assert_eq!(start(Alignment::Left), Some(0));
+        assert_eq!(start(Alignment::Center), Some(3));
+        assert_eq!(start(Alignment::Right), Some(6));
+
+        let mut buffer = Buffer::empty(10, 2);
+        buffer.set_line_aligned(Rect::new(1, 1, 3, 1), &line, Alignment::Right);
+        assert_eq!(buffer.to_lines(), vec!["          ", " abc      "]);
+    }
+
+    #[test]
+    fn set_skip_marks_region() {
+        let mut buffer = Buffer::empty(5, 5);
+        buffer.set_string(0, 1, "abcde", Style::default().fg(Color::Red));
+        buffer.set_skip(Rect::new(1, 1, 3, 3), true);
+
+        let drawn: Vec<_> = buffer.draw().map(|(x, y, _)| (x, y)).collect();
+        assert_eq!(drawn.len(), 16);
+        assert!(drawn
+            .iter()
+            .all(|&(x, y)| !Rect::new(1, 1, 3, 3).contains(x, y)));
+        assert_eq!(buffer[(2, 1)].symbol, "c");
+        assert_eq!(buffer[(2, 1)].fg, Color::Red);
+
+        buffer.set_skip(Rect::new(3, 3, 10, 10), false);
+        assert_eq!(buffer.draw().count(), 17);
+    }
+}
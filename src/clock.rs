@@ -0,0 +1,40 @@
+use std::fmt;
+use std::time::Instant;
+
+/// A source of time for the `WindowManager`, replaceable to control time in tests.
+pub trait Clock: fmt::Debug {
+    fn now(&self) -> Instant;
+}
+
+/// The system's monotonic clock.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to, for tests.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub(crate) struct MockClock(std::rc::Rc<std::cell::Cell<Instant>>);
+
+#[cfg(test)]
+impl MockClock {
+    pub(crate) fn new() -> Self {
+        MockClock(std::rc::Rc::new(std::cell::Cell::new(Instant::now())))
+    }
+
+    pub(crate) fn advance(&self, duration: std::time::Duration) {
+        self.0.set(self.0.get() + duration);
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.0.get()
+    }
+}
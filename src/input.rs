@@ -0,0 +1,263 @@
+use std::io;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::terminal::Terminal;
+use crate::text::Style;
+use crate::window::{Rect, Window};
+
+/// A single-line text input.
+///
+/// The caret moves by grapheme, so multi-byte and multi-width characters are edited as a whole.
+/// When the text is wider than the window it scrolls horizontally to keep the caret in view.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Input {
+    window: Window,
+    value: String,
+    /// Byte index of the caret, always on a grapheme boundary.
+    cursor: usize,
+    /// Number of columns scrolled out on the left.
+    offset: usize,
+    style: Style,
+}
+
+impl Input {
+    pub fn new(area: Rect) -> Self {
+        Input {
+            window: Window::new(area),
+            value: String::new(),
+            cursor: 0,
+            offset: 0,
+            style: Style::default(),
+        }
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn window_mut(&mut self) -> &mut Window {
+        &mut self.window
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Replaces the text and moves the caret to the end.
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        self.value = value.into();
+        self.cursor = self.value.len();
+        self.scroll_to_cursor();
+    }
+
+    /// Byte index of the caret in `value`.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    pub fn insert_char(&mut self, ch: char) {
+        self.value.insert(self.cursor, ch);
+        self.cursor += ch.len_utf8();
+        // A combining character may have merged with the previous grapheme.
+        self.cursor = self.grapheme_end(self.cursor);
+        self.scroll_to_cursor();
+    }
+
+    /// Removes the grapheme before the caret.
+    pub fn backspace(&mut self) {
+        if let Some(start) = self.prev_boundary() {
+            self.value.replace_range(start..self.cursor, "");
+            self.cursor = start;
+            self.scroll_to_cursor();
+        }
+    }
+
+    /// Removes the grapheme after the caret.
+    pub fn delete(&mut self) {
+        if let Some(end) = self.next_boundary() {
+            self.value.replace_range(self.cursor..end, "");
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if let Some(i) = self.prev_boundary() {
+            self.cursor = i;
+            self.scroll_to_cursor();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if let Some(i) = self.next_boundary() {
+            self.cursor = i;
+            self.scroll_to_cursor();
+        }
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+        self.scroll_to_cursor();
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.value.len();
+        self.scroll_to_cursor();
+    }
+
+    /// Applies an editing key, returning `true` if the key was handled.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(ch)
+                if key.modifiers == KeyModifiers::NONE || key.modifiers == KeyModifiers::SHIFT =>
+            {
+                self.insert_char(ch)
+            }
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.move_home(),
+            KeyCode::End => self.move_end(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Draws the visible part of the text into the first row of the window.
+    pub fn render(&mut self) {
+        let width = self.window.width() as usize;
+        let mut visible = String::new();
+        let mut column = 0;
+        for g in self.value.graphemes(true) {
+            let start = column;
+            column += g.width();
+            if start < self.offset {
+                // Pad what is left of a wide grapheme cut off by the left edge.
+                visible.extend(std::iter::repeat_n(' ', column.saturating_sub(self.offset)));
+                continue;
+            }
+            if column - self.offset > width {
+                break;
+            }
+            visible.push_str(g);
+        }
+
+        self.window.reset();
+        self.window.set_stringn(0, 0, visible, width, self.style);
+    }
+
+    /// Global position of the caret on the screen.
+    pub fn cursor_position(&self) -> (u16, u16) {
+        let (x, y) = self.window.pos();
+        let column = self.value[..self.cursor].width() - self.offset;
+        (x + column as u16, y)
+    }
+
+    /// Moves the hardware cursor to the caret.
+    pub fn place_cursor(&self) -> io::Result<()> {
+        let (x, y) = self.cursor_position();
+        Terminal::set_cursor_pos(x, y)
+    }
+
+    fn prev_boundary(&self) -> Option<usize> {
+        self.value[..self.cursor]
+            .graphemes(true)
+            .next_back()
+            .map(|g| self.cursor - g.len())
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.value[self.cursor..]
+            .graphemes(true)
+            .next()
+            .map(|g| self.cursor + g.len())
+    }
+
+    fn grapheme_end(&self, index: usize) -> usize {
+        self.value
+            .grapheme_indices(true)
+            .map(|(i, g)| i + g.len())
+            .find(|&end| end >= index)
+            .unwrap_or(index)
+    }
+
+    fn scroll_to_cursor(&mut self) {
+        // Keep one column free for the caret at the end of the text.
+        let width = (self.window.width() as usize).max(1);
+        let column = self.value[..self.cursor].width();
+        if column < self.offset {
+            self.offset = column;
+        } else if column >= self.offset + width {
+            self.offset = column + 1 - width;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(input: &Input) -> String {
+        let buf = input.window().buffer();
+        (0..buf.width)
+            .map(|x| buf.content[buf.index_of(x, 0)].symbol.as_str())
+            .collect()
+    }
+
+    fn type_str(input: &mut Input, s: &str) {
+        for ch in s.chars() {
+            input.insert_char(ch);
+        }
+    }
+
+    #[test]
+    fn edit_multibyte() {
+        let mut input = Input::new(Rect::new(3, 2, 10, 1));
+        type_str(&mut input, "añ日b");
+        assert_eq!(input.value(), "añ日b");
+        assert_eq!(input.cursor_position(), (3 + 5, 2));
+
+        input.move_left();
+        input.backspace();
+        assert_eq!(input.value(), "añb");
+        assert_eq!(input.cursor(), "añ".len());
+        assert_eq!(input.cursor_position(), (3 + 2, 2));
+
+        input.move_home();
+        input.delete();
+        assert_eq!(input.value(), "ñb");
+        assert_eq!(input.cursor(), 0);
+
+        input.render();
+        assert_eq!(row(&input), "ñb        ");
+    }
+
+    #[test]
+    fn combining_character_is_one_grapheme() {
+        let mut input = Input::new(Rect::new(0, 0, 10, 1));
+        type_str(&mut input, "e\u{301}x");
+        input.move_left();
+        input.backspace();
+        assert_eq!(input.value(), "x");
+    }
+
+    #[test]
+    fn scrolls_horizontally() {
+        let mut input = Input::new(Rect::new(0, 0, 5, 1));
+        type_str(&mut input, "abcdefg");
+        input.render();
+        assert_eq!(row(&input), "defg ");
+        assert_eq!(input.cursor_position(), (4, 0));
+
+        input.move_home();
+        input.render();
+        assert_eq!(row(&input), "abcde");
+        assert_eq!(input.cursor_position(), (0, 0));
+    }
+}
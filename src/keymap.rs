@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A window management action `WindowManager` performs when its key is pressed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Action {
+    /// Exit the event loop.
+    Quit,
+    /// Focus the next window.
+    CycleNext,
+    /// Focus the previous window.
+    CyclePrevious,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    ResizeLeft,
+    ResizeRight,
+    ResizeUp,
+    ResizeDown,
+    /// Move the focused window one step up the stack.
+    Raise,
+    /// Move the focused window one step down the stack.
+    Lower,
+    /// Minimize the focused window, or restore it if it is minimized.
+    ToggleMinimize,
+    /// Close the focused window.
+    Close,
+}
+
+/// Maps keys to the window management actions of `WindowManager`.
+///
+/// Keys are matched on their code and modifiers, whatever their kind. `WindowManager` only acts
+/// on presses, so holding a key down doesn't repeat its action. The default map has the bindings
+/// listed on `WindowManager::handle_manager_keys`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap {
+    bindings: HashMap<(KeyCode, KeyModifiers), Action>,
+}
+
+impl KeyMap {
+    /// A map without any bindings, leaving every key to the windows.
+    pub fn empty() -> Self {
+        KeyMap {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Makes `key` trigger `action`, replacing what `key` was bound to before. An action can
+    /// have several keys.
+    pub fn bind(&mut self, key: KeyEvent, action: Action) {
+        self.bindings.insert((key.code, key.modifiers), action);
+    }
+
+    /// Removes every key bound to `action`.
+    pub fn unbind(&mut self, action: Action) {
+        self.bindings.retain(|_, bound| *bound != action);
+    }
+
+    /// Returns the action bound to `key`, if any.
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        self.bindings.get(&(key.code, key.modifiers)).copied()
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let ctrl = KeyModifiers::CONTROL;
+        let ctrl_shift = KeyModifiers::CONTROL | KeyModifiers::SHIFT;
+        let mut map = KeyMap::empty();
+        let bindings = [
+            (KeyCode::Char('c'), ctrl, Action::Quit),
+            (KeyCode::Tab, KeyModifiers::NONE, Action::CycleNext),
+            (KeyCode::Tab, KeyModifiers::SHIFT, Action::CyclePrevious),
+            (KeyCode::BackTab, KeyModifiers::NONE, Action::CyclePrevious),
+            (KeyCode::BackTab, KeyModifiers::SHIFT, Action::CyclePrevious),
+            (KeyCode::Left, ctrl, Action::MoveLeft),
+            (KeyCode::Right, ctrl, Action::MoveRight),
+            (KeyCode::Up, ctrl, Action::MoveUp),
+            (KeyCode::Down, ctrl, Action::MoveDown),
+            (KeyCode::Left, ctrl_shift, Action::ResizeLeft),
+            (KeyCode::Right, ctrl_shift, Action::ResizeRight),
+            (KeyCode::Up, ctrl_shift, Action::ResizeUp),
+            (KeyCode::Down, ctrl_shift, Action::ResizeDown),
+            (KeyCode::PageUp, KeyModifiers::NONE, Action::Raise),
+            (KeyCode::PageDown, KeyModifiers::NONE, Action::Lower),
+            // Ctrl+z rather than Ctrl+m, which arrives as Enter without keyboard enhancement.
+            (KeyCode::Char('z'), ctrl, Action::ToggleMinimize),
+            (KeyCode::Char('w'), ctrl, Action::Close),
+        ];
+        for (code, modifiers, action) in bindings {
+            map.bind(KeyEvent::new(code, modifiers), action);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_and_unbind() {
+        let mut map = KeyMap::default();
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        let ctrl_q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert_eq!(map.action(ctrl_c), Some(Action::Quit));
+        assert_eq!(map.action(ctrl_q), None);
+
+        map.bind(ctrl_q, Action::Quit);
+        assert_eq!(map.action(ctrl_c), Some(Action::Quit));
+        assert_eq!(map.action(ctrl_q), Some(Action::Quit));
+
+        map.unbind(Action::Quit);
+        assert_eq!(map.action(ctrl_c), None);
+        assert_eq!(map.action(ctrl_q), None);
+
+        map.bind(ctrl_c, Action::Close);
+        assert_eq!(map.action(ctrl_c), Some(Action::Close));
+    }
+}
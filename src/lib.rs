@@ -1,30 +1,49 @@
+pub mod backend;
 pub mod buffer;
+pub mod clock;
+pub mod input;
+pub mod keymap;
+pub mod list;
+#[cfg(feature = "palettes")]
+pub mod palettes;
+mod render;
 pub mod terminal;
 pub mod text;
+pub mod theme;
+pub mod widget;
 pub mod window;
+pub mod window_manager;
+
+pub mod prelude {
+    pub use crate::backend::{Backend, CrosstermBackend};
+    pub use crate::buffer::{Buffer, Cell};
+    pub use crate::input::Input;
+    pub use crate::keymap::KeyMap;
+    pub use crate::list::List;
+    pub use crate::terminal::Terminal;
+    pub use crate::text::{Line, Modifier, Span, Style};
+    pub use crate::theme::Theme;
+    pub use crate::widget::Widget;
+    pub use crate::window::{Rect, Window};
+    pub use crate::window_manager::WindowManager;
+}
 
 #[cfg(test)]
 mod tests {
-    use crossterm::{
-        cursor::{Hide, Show},
-        execute,
-        terminal::{EnterAlternateScreen, LeaveAlternateScreen},
-    };
+    use crossterm::{execute, terminal::LeaveAlternateScreen};
     use std::io;
 
+    use crate::terminal::ScreenGuard;
     use crate::window::{Rect, Window};
 
     #[test]
     fn it_works() -> io::Result<()> {
-        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
-        crossterm::terminal::enable_raw_mode()?;
-
-        let (width, height) = crossterm::terminal::size()?;
+        let _guard = ScreenGuard::new()?;
 
-        let win = Window::new(Rect::new(4, 4, 30, 20));
+        let (_width, _height) = crossterm::terminal::size()?;
 
-        crossterm::terminal::disable_raw_mode()?;
-        execute!(io::stdout(), LeaveAlternateScreen, Show)
+        let _win = Window::new(Rect::new(4, 4, 30, 20));
+        Ok(())
     }
 
     #[test]
@@ -0,0 +1,204 @@
+use crate::text::{Modifier, Style};
+use crate::window::{Rect, Window};
+
+/// A bordered, scrollable list of items with an optional selection.
+///
+/// The items are rendered into the inner area of the window (inside the border), and the view
+/// scrolls automatically to keep the selected item visible.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct List {
+    window: Window,
+    items: Vec<String>,
+    selected: Option<usize>,
+    offset: usize,
+    wrap_around: bool,
+    style: Style,
+    highlight_style: Style,
+}
+
+impl List {
+    pub fn new(area: Rect, items: Vec<String>) -> Self {
+        List {
+            window: Window::new(area),
+            items,
+            selected: None,
+            offset: 0,
+            wrap_around: false,
+            style: Style::default(),
+            highlight_style: Style::default().add_modifier(Modifier::REVERSED),
+        }
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn window_mut(&mut self) -> &mut Window {
+        &mut self.window
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    /// Replaces the items, clearing the selection if it no longer points at an item.
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        if matches!(self.selected, Some(i) if i >= self.items.len()) {
+            self.selected = None;
+        }
+        self.offset = 0;
+        self.scroll_to_selected();
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    /// Index of the first visible item.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selected = index.filter(|&i| i < self.items.len());
+        self.scroll_to_selected();
+    }
+
+    /// Whether `select_next` / `select_previous` wrap around at the ends of the list.
+    pub fn set_wrap_around(&mut self, wrap_around: bool) {
+        self.wrap_around = wrap_around;
+    }
+
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    pub fn set_highlight_style(&mut self, style: Style) {
+        self.highlight_style = style;
+    }
+
+    pub fn select_next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        let last = self.items.len() - 1;
+        self.selected = Some(match self.selected {
+            None => 0,
+            Some(i) if i >= last && self.wrap_around => 0,
+            Some(i) => (i + 1).min(last),
+        });
+        self.scroll_to_selected();
+    }
+
+    pub fn select_previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        let last = self.items.len() - 1;
+        self.selected = Some(match self.selected {
+            None => last,
+            Some(0) if self.wrap_around => last,
+            Some(i) => i.saturating_sub(1),
+        });
+        self.scroll_to_selected();
+    }
+
+    /// Draws the border and the visible items into the window.
+    pub fn render(&mut self) {
+        let (width, height) = self.inner_size();
+
+        self.window.reset();
+        self.window.draw_border("");
+
+        for row in 0..height {
+            let index = self.offset + row as usize;
+            let Some(item) = self.items.get(index) else {
+                break;
+            };
+
+            self.window
+                .set_stringn(1, 1 + row, item, width as usize, self.style);
+            if self.selected == Some(index) {
+                self.window
+                    .set_style(Rect::new(1, 1 + row, width, 1), self.highlight_style);
+            }
+        }
+    }
+
+    fn inner_size(&self) -> (u16, u16) {
+        (
+            self.window.width().saturating_sub(2),
+            self.window.height().saturating_sub(2),
+        )
+    }
+
+    fn scroll_to_selected(&mut self) {
+        let Some(selected) = self.selected else {
+            return;
+        };
+        let visible = self.inner_size().1.max(1) as usize;
+        if selected < self.offset {
+            self.offset = selected;
+        } else if selected >= self.offset + visible {
+            self.offset = selected + 1 - visible;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(len: usize) -> List {
+        let items = (0..len).map(|i| format!("item {i}")).collect();
+        // 3 visible rows inside the border
+        List::new(Rect::new(0, 0, 12, 5), items)
+    }
+
+    #[test]
+    fn offset_follows_selection_down() {
+        let mut list = list(10);
+        for _ in 0..5 {
+            list.select_next();
+        }
+        assert_eq!(list.selected(), Some(4));
+        assert_eq!(list.offset(), 2);
+
+        list.render();
+        let buf = list.window().buffer();
+        let cell = &buf.content[buf.index_of(1, 3)];
+        assert_eq!(cell.symbol, "i");
+        assert!(cell.modifier.contains(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn offset_follows_selection_up() {
+        let mut list = list(10);
+        list.select(Some(9));
+        assert_eq!(list.offset(), 7);
+
+        for _ in 0..4 {
+            list.select_previous();
+        }
+        assert_eq!(list.selected(), Some(5));
+        assert_eq!(list.offset(), 5);
+    }
+
+    #[test]
+    fn wrap_around() {
+        let mut list = list(10);
+        list.select(Some(9));
+        list.select_next();
+        assert_eq!(list.selected(), Some(9));
+
+        list.set_wrap_around(true);
+        list.select_next();
+        assert_eq!(list.selected(), Some(0));
+        assert_eq!(list.offset(), 0);
+
+        list.select_previous();
+        assert_eq!(list.selected(), Some(9));
+        assert_eq!(list.offset(), 7);
+    }
+}
@@ -0,0 +1,94 @@
+//! Color constants for popular themes, e.g. `Style::default().fg(palettes::nord::FROST_1)`.
+
+use crossterm::style::Color;
+
+const fn hex(rgb: u32) -> Color {
+    Color::Rgb {
+        r: (rgb >> 16) as u8,
+        g: (rgb >> 8) as u8,
+        b: rgb as u8,
+    }
+}
+
+/// The [Nord](https://www.nordtheme.com/) palette.
+pub mod nord {
+    use super::hex;
+    use crossterm::style::Color;
+
+    pub const POLAR_NIGHT_0: Color = hex(0x2e3440);
+    pub const POLAR_NIGHT_1: Color = hex(0x3b4252);
+    pub const POLAR_NIGHT_2: Color = hex(0x434c5e);
+    pub const POLAR_NIGHT_3: Color = hex(0x4c566a);
+    pub const SNOW_STORM_0: Color = hex(0xd8dee9);
+    pub const SNOW_STORM_1: Color = hex(0xe5e9f0);
+    pub const SNOW_STORM_2: Color = hex(0xeceff4);
+    pub const FROST_0: Color = hex(0x8fbcbb);
+    pub const FROST_1: Color = hex(0x88c0d0);
+    pub const FROST_2: Color = hex(0x81a1c1);
+    pub const FROST_3: Color = hex(0x5e81ac);
+    pub const RED: Color = hex(0xbf616a);
+    pub const ORANGE: Color = hex(0xd08770);
+    pub const YELLOW: Color = hex(0xebcb8b);
+    pub const GREEN: Color = hex(0xa3be8c);
+    pub const PURPLE: Color = hex(0xb48ead);
+}
+
+/// The [Catppuccin](https://catppuccin.com/) Mocha palette.
+pub mod catppuccin_mocha {
+    use super::hex;
+    use crossterm::style::Color;
+
+    pub const ROSEWATER: Color = hex(0xf5e0dc);
+    pub const FLAMINGO: Color = hex(0xf2cdcd);
+    pub const PINK: Color = hex(0xf5c2e7);
+    pub const MAUVE: Color = hex(0xcba6f7);
+    pub const RED: Color = hex(0xf38ba8);
+    pub const MAROON: Color = hex(0xeba0ac);
+    pub const PEACH: Color = hex(0xfab387);
+    pub const YELLOW: Color = hex(0xf9e2af);
+    pub const GREEN: Color = hex(0xa6e3a1);
+    pub const TEAL: Color = hex(0x94e2d5);
+    pub const SKY: Color = hex(0x89dceb);
+    pub const SAPPHIRE: Color = hex(0x74c7ec);
+    pub const BLUE: Color = hex(0x89b4fa);
+    pub const LAVENDER: Color = hex(0xb4befe);
+    pub const TEXT: Color = hex(0xcdd6f4);
+    pub const SUBTEXT_1: Color = hex(0xbac2de);
+    pub const SUBTEXT_0: Color = hex(0xa6adc8);
+    pub const OVERLAY_2: Color = hex(0x9399b2);
+    pub const OVERLAY_1: Color = hex(0x7f849c);
+    pub const OVERLAY_0: Color = hex(0x6c7086);
+    pub const SURFACE_2: Color = hex(0x585b70);
+    pub const SURFACE_1: Color = hex(0x45475a);
+    pub const SURFACE_0: Color = hex(0x313244);
+    pub const BASE: Color = hex(0x1e1e2e);
+    pub const MANTLE: Color = hex(0x181825);
+    pub const CRUST: Color = hex(0x11111b);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::Style;
+
+    #[test]
+    fn palette_values() {
+        assert_eq!(
+            nord::FROST_1,
+            Color::Rgb {
+                r: 0x88,
+                g: 0xc0,
+                b: 0xd0
+            }
+        );
+        assert_eq!(
+            catppuccin_mocha::BASE,
+            Color::Rgb {
+                r: 0x1e,
+                g: 0x1e,
+                b: 0x2e
+            }
+        );
+        assert_eq!(Style::default().fg(nord::RED).fg, Some(nord::RED));
+    }
+}
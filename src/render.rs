@@ -0,0 +1,512 @@
+//! Escape sequence output shared by `Terminal` and `WindowManager`.
+
+use crossterm::cursor::{MoveDown, MoveLeft, MoveRight, MoveTo, MoveUp};
+use crossterm::queue;
+use crossterm::style::{
+    Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+    SetUnderlineColor,
+};
+use std::io;
+use unicode_width::UnicodeWidthStr;
+
+use crate::buffer::Cell;
+use crate::text::Modifier;
+
+/// How `render_cells` writes cells.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub(crate) struct RenderOptions {
+    /// Use relative cursor moves where they are shorter than absolute ones.
+    pub relative_moves: bool,
+    /// Replace box drawing characters and common symbols with ASCII, see `ascii_symbol`.
+    pub ascii: bool,
+    /// Leave out cursor moves, starting a new line where the row changes instead, for output
+    /// that ends up in a file rather than on a screen.
+    pub strip_cursor_moves: bool,
+}
+
+/// Writes `cells` at their positions, only emitting the cursor moves, colors and modifiers that
+/// change from one cell to the next, and resets the colors and modifiers at the end.
+pub(crate) fn render_cells<'a, W, I>(
+    mut writer: W,
+    cells: I,
+    options: RenderOptions,
+) -> io::Result<()>
+where
+    W: io::Write,
+    I: Iterator<Item = (u16, u16, &'a Cell)>,
+{
+    let mut fg = Color::Reset;
+    let mut bg = Color::Reset;
+    let mut underline_color = Color::Reset;
+    let mut modifier = Modifier::empty();
+    let mut link: Option<&str> = None;
+    // Where the cursor is after printing the previous cell.
+    let mut cursor: Option<(u16, u16)> = None;
+    for (x, y, cell) in cells {
+        if options.strip_cursor_moves {
+            if cursor.is_some_and(|(_, row)| row != y) {
+                queue!(writer, Print("\n"))?;
+            }
+        } else if cursor != Some((x, y)) {
+            match cursor {
+                Some(from) if options.relative_moves => queue_move(&mut writer, from, (x, y))?,
+                _ => queue!(writer, MoveTo(x, y))?,
+            }
+        }
+        if cell.modifier != modifier {
+            let diff = ModifierDiff {
+                from: modifier,
+                to: cell.modifier,
+            };
+            diff.queue(&mut writer)?;
+            modifier = cell.modifier;
+        }
+        if cell.fg != fg {
+            queue!(writer, SetForegroundColor(cell.fg))?;
+            fg = cell.fg;
+        }
+        if cell.bg != bg {
+            queue!(writer, SetBackgroundColor(cell.bg))?;
+            bg = cell.bg;
+        }
+        if cell.underline_color != underline_color {
+            queue!(writer, SetUnderlineColor(cell.underline_color))?;
+            underline_color = cell.underline_color;
+        }
+
+        if cell.hyperlink.as_deref() != link {
+            queue_hyperlink(&mut writer, link, cell.hyperlink.as_deref())?;
+            link = cell.hyperlink.as_deref();
+        }
+
+        let symbol = if options.ascii {
+            ascii_symbol(&cell.symbol)
+        } else {
+            &cell.symbol
+        };
+        queue!(writer, Print(symbol))?;
+        // Wide graphemes move the cursor by more than one column.
+        cursor = Some((x.saturating_add(symbol.width() as u16), y));
+    }
+    queue_hyperlink(&mut writer, link, None)?;
+
+    queue!(
+        writer,
+        SetForegroundColor(Color::Reset),
+        SetBackgroundColor(Color::Reset),
+        SetAttribute(Attribute::Reset),
+    )
+}
+
+/// Returns an ASCII stand-in for box drawing characters and a few common symbols, for
+/// terminals that can't display them. Other symbols are returned unchanged.
+fn ascii_symbol(symbol: &str) -> &str {
+    match symbol {
+        "─" | "━" | "═" | "┄" | "┈" => "-",
+        "│" | "┃" | "║" | "┆" | "┊" => "|",
+        "┌" | "┐" | "└" | "┘" | "╭" | "╮" | "╰" | "╯" | "╔" | "╗" | "╚" | "╝" | "┏" | "┓" | "┗"
+        | "┛" | "├" | "┤" | "┬" | "┴" | "┼" => "+",
+        "…" | "·" => ".",
+        "•" => "*",
+        "█" | "▓" => "#",
+        "▒" => ":",
+        "░" => ".",
+        "←" => "<",
+        "→" => ">",
+        "↑" => "^",
+        "↓" => "v",
+        "◢" => "/",
+        _ => symbol,
+    }
+}
+
+/// Queues the OSC 8 sequences ending the hyperlink `from` and starting the hyperlink `to`.
+pub(crate) fn queue_hyperlink<W>(mut w: W, from: Option<&str>, to: Option<&str>) -> io::Result<()>
+where
+    W: io::Write,
+{
+    if from.is_some() {
+        queue!(w, Print("\x1b]8;;\x1b\\"))?;
+    }
+    if let Some(url) = to {
+        queue!(w, Print(format!("\x1b]8;;{url}\x1b\\")))?;
+    }
+    Ok(())
+}
+
+/// Queues the shortest escape sequence moving the cursor from `from` to `to`, choosing between
+/// an absolute `MoveTo` and a combination of relative moves.
+fn queue_move<W>(mut w: W, from: (u16, u16), to: (u16, u16)) -> io::Result<()>
+where
+    W: io::Write,
+{
+    fn digits(n: u16) -> usize {
+        n.checked_ilog10().unwrap_or(0) as usize + 1
+    }
+
+    let dx = to.0.abs_diff(from.0);
+    let dy = to.1.abs_diff(from.1);
+
+    // `MoveTo` is `ESC [ row ; col H`, relative moves are `ESC [ n A/B/C/D`.
+    let absolute = 4 + digits(to.1 + 1) + digits(to.0 + 1);
+    let mut relative = 0;
+    if dx != 0 {
+        relative += 3 + digits(dx);
+    }
+    if dy != 0 {
+        relative += 3 + digits(dy);
+    }
+
+    if relative >= absolute {
+        return queue!(w, MoveTo(to.0, to.1));
+    }
+
+    if to.1 < from.1 {
+        queue!(w, MoveUp(dy))?;
+    } else if to.1 > from.1 {
+        queue!(w, MoveDown(dy))?;
+    }
+    if to.0 < from.0 {
+        queue!(w, MoveLeft(dx))?;
+    } else if to.0 > from.0 {
+        queue!(w, MoveRight(dx))?;
+    }
+    Ok(())
+}
+
+/// The `ModifierDiff` struct is used to calculate the difference between two `Modifier`
+/// values. This is useful when updating the terminal display, as it allows for more
+/// efficient updates by only sending the necessary changes.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub(crate) struct ModifierDiff {
+    pub from: Modifier,
+    pub to: Modifier,
+}
+
+impl ModifierDiff {
+    pub(crate) fn queue<W>(&self, mut w: W) -> io::Result<()>
+    where
+        W: io::Write,
+    {
+        //use crossterm::Attribute;
+        let removed = self.from - self.to;
+        if removed.contains(Modifier::REVERSED) {
+            queue!(w, SetAttribute(Attribute::NoReverse))?;
+        }
+        let intensities = Modifier::BOLD | Modifier::DIM;
+        if removed.intersects(intensities) {
+            // `NormalIntensity` turns off both bold and dim, so restore the one that is kept.
+            queue!(w, SetAttribute(Attribute::NormalIntensity))?;
+            let kept = self.from & self.to & intensities;
+            if kept.contains(Modifier::BOLD) {
+                queue!(w, SetAttribute(Attribute::Bold))?;
+            }
+            if kept.contains(Modifier::DIM) {
+                queue!(w, SetAttribute(Attribute::Dim))?;
+            }
+        }
+        if removed.contains(Modifier::ITALIC) {
+            queue!(w, SetAttribute(Attribute::NoItalic))?;
+        }
+        let underlines = Modifier::UNDERLINED | Modifier::DOUBLE_UNDERLINED;
+        if removed.intersects(underlines) {
+            // `NoUnderline` turns off both kinds, so restore the one that is kept, if any.
+            queue!(w, SetAttribute(Attribute::NoUnderline))?;
+            let kept = self.from & self.to & underlines;
+            if kept.contains(Modifier::UNDERLINED) {
+                queue!(w, SetAttribute(Attribute::Underlined))?;
+            }
+            if kept.contains(Modifier::DOUBLE_UNDERLINED) {
+                queue!(w, SetAttribute(Attribute::DoubleUnderlined))?;
+            }
+        }
+        if removed.contains(Modifier::CROSSED_OUT) {
+            queue!(w, SetAttribute(Attribute::NotCrossedOut))?;
+        }
+        let blinks = Modifier::SLOW_BLINK | Modifier::RAPID_BLINK;
+        if removed.intersects(blinks) {
+            // Same for `NoBlink` and the two blink speeds.
+            queue!(w, SetAttribute(Attribute::NoBlink))?;
+            let kept = self.from & self.to & blinks;
+            if kept.contains(Modifier::SLOW_BLINK) {
+                queue!(w, SetAttribute(Attribute::SlowBlink))?;
+            }
+            if kept.contains(Modifier::RAPID_BLINK) {
+                queue!(w, SetAttribute(Attribute::RapidBlink))?;
+            }
+        }
+        if removed.contains(Modifier::OVERLINED) {
+            queue!(w, SetAttribute(Attribute::NotOverLined))?;
+        }
+        if removed.contains(Modifier::HIDDEN) {
+            queue!(w, SetAttribute(Attribute::NoHidden))?;
+        }
+
+        let added = self.to - self.from;
+        if added.contains(Modifier::REVERSED) {
+            queue!(w, SetAttribute(Attribute::Reverse))?;
+        }
+        if added.contains(Modifier::BOLD) {
+            queue!(w, SetAttribute(Attribute::Bold))?;
+        }
+        if added.contains(Modifier::ITALIC) {
+            queue!(w, SetAttribute(Attribute::Italic))?;
+        }
+        if added.contains(Modifier::UNDERLINED) {
+            queue!(w, SetAttribute(Attribute::Underlined))?;
+        }
+        if added.contains(Modifier::DIM) {
+            queue!(w, SetAttribute(Attribute::Dim))?;
+        }
+        if added.contains(Modifier::CROSSED_OUT) {
+            queue!(w, SetAttribute(Attribute::CrossedOut))?;
+        }
+        if added.contains(Modifier::SLOW_BLINK) {
+            queue!(w, SetAttribute(Attribute::SlowBlink))?;
+        }
+        if added.contains(Modifier::RAPID_BLINK) {
+            queue!(w, SetAttribute(Attribute::RapidBlink))?;
+        }
+        if added.contains(Modifier::OVERLINED) {
+            queue!(w, SetAttribute(Attribute::OverLined))?;
+        }
+        if added.contains(Modifier::DOUBLE_UNDERLINED) {
+            queue!(w, SetAttribute(Attribute::DoubleUnderlined))?;
+        }
+        if added.contains(Modifier::HIDDEN) {
+            queue!(w, SetAttribute(Attribute::Hidden))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_cells_output() {
+        let mut red = Cell::default();
+        red.set_symbol("a").set_fg(Color::Red);
+        let mut bold = red.clone();
+        bold.set_symbol("b").modifier = Modifier::BOLD;
+        let cells = [(2, 1, &red), (3, 1, &bold), (0, 4, &red)];
+
+        let mut out = Vec::new();
+        render_cells(&mut out, cells.into_iter(), RenderOptions::default()).unwrap();
+
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            MoveTo(2, 1),
+            SetForegroundColor(Color::Red),
+            Print("a"),
+            SetAttribute(Attribute::Bold),
+            Print("b"),
+            MoveTo(0, 4),
+            SetAttribute(Attribute::NormalIntensity),
+            Print("a"),
+            SetForegroundColor(Color::Reset),
+            SetBackgroundColor(Color::Reset),
+            SetAttribute(Attribute::Reset),
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(out), String::from_utf8(expected));
+    }
+
+    #[test]
+    fn wide_symbols_advance_the_cursor_by_their_width() {
+        let mut wide = Cell::default();
+        wide.set_symbol("世");
+        let mut narrow = Cell::default();
+        narrow.set_symbol("a");
+
+        let mut out = Vec::new();
+        let cells = [
+            (0, 0, &wide),
+            (2, 0, &narrow),
+            (3, 0, &wide),
+            (4, 0, &narrow),
+        ];
+        render_cells(&mut out, cells.into_iter(), RenderOptions::default()).unwrap();
+
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            MoveTo(0, 0),
+            Print("世"),
+            Print("a"),
+            Print("世"),
+            MoveTo(4, 0),
+            Print("a"),
+            SetForegroundColor(Color::Reset),
+            SetBackgroundColor(Color::Reset),
+            SetAttribute(Attribute::Reset),
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(out), String::from_utf8(expected));
+    }
+
+    #[test]
+    fn relative_move_is_preferred_when_shorter() {
+        let mut relative = Vec::new();
+        queue_move(&mut relative, (6, 3), (6, 4)).unwrap();
+
+        let mut absolute = Vec::new();
+        queue!(absolute, MoveTo(6, 4)).unwrap();
+
+        assert_eq!(relative, b"\x1b[1B");
+        assert!(relative.len() < absolute.len());
+    }
+
+    #[test]
+    fn absolute_move_is_kept_when_shorter() {
+        let mut out = Vec::new();
+        queue_move(&mut out, (60, 3), (2, 40)).unwrap();
+
+        assert_eq!(out, b"\x1b[41;3H");
+    }
+
+    #[test]
+    fn modifier_diff_swaps_modifiers() {
+        let mut out = Vec::new();
+        let diff = ModifierDiff {
+            from: Modifier::BOLD | Modifier::ITALIC,
+            to: Modifier::ITALIC | Modifier::REVERSED,
+        };
+        diff.queue(&mut out).unwrap();
+
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            SetAttribute(Attribute::NormalIntensity),
+            SetAttribute(Attribute::Reverse)
+        )
+        .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    fn queued(from: Modifier, to: Modifier) -> Vec<u8> {
+        let mut out = Vec::new();
+        ModifierDiff { from, to }.queue(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn modifier_diff_keeps_dim_when_removing_bold() {
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            SetAttribute(Attribute::NormalIntensity),
+            SetAttribute(Attribute::Dim)
+        )
+        .unwrap();
+        assert_eq!(
+            queued(Modifier::BOLD | Modifier::DIM, Modifier::DIM),
+            expected
+        );
+
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            SetAttribute(Attribute::NormalIntensity),
+            SetAttribute(Attribute::Bold)
+        )
+        .unwrap();
+        assert_eq!(
+            queued(Modifier::BOLD | Modifier::DIM, Modifier::BOLD),
+            expected
+        );
+    }
+
+    #[test]
+    fn modifier_diff_removes_bold_and_dim_at_once() {
+        let mut expected = Vec::new();
+        queue!(expected, SetAttribute(Attribute::NormalIntensity)).unwrap();
+        assert_eq!(
+            queued(Modifier::BOLD | Modifier::DIM, Modifier::empty()),
+            expected
+        );
+    }
+
+    #[test]
+    fn modifier_diff_keeps_other_blink() {
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            SetAttribute(Attribute::NoBlink),
+            SetAttribute(Attribute::RapidBlink)
+        )
+        .unwrap();
+        let both = Modifier::SLOW_BLINK | Modifier::RAPID_BLINK;
+        assert_eq!(queued(both, Modifier::RAPID_BLINK), expected);
+    }
+
+    #[test]
+    fn modifier_diff_hidden() {
+        let mut out = Vec::new();
+        let on = ModifierDiff {
+            from: Modifier::BOLD,
+            to: Modifier::BOLD | Modifier::HIDDEN,
+        };
+        on.queue(&mut out).unwrap();
+        let off = ModifierDiff {
+            from: Modifier::BOLD | Modifier::HIDDEN,
+            to: Modifier::BOLD,
+        };
+        off.queue(&mut out).unwrap();
+
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            SetAttribute(Attribute::Hidden),
+            SetAttribute(Attribute::NoHidden)
+        )
+        .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn modifier_diff_overlined() {
+        let mut out = Vec::new();
+        let on = ModifierDiff {
+            from: Modifier::empty(),
+            to: Modifier::OVERLINED,
+        };
+        on.queue(&mut out).unwrap();
+        let off = ModifierDiff {
+            from: Modifier::OVERLINED,
+            to: Modifier::empty(),
+        };
+        off.queue(&mut out).unwrap();
+
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            SetAttribute(Attribute::OverLined),
+            SetAttribute(Attribute::NotOverLined)
+        )
+        .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn modifier_diff_keeps_other_underline() {
+        let mut out = Vec::new();
+        let diff = ModifierDiff {
+            from: Modifier::UNDERLINED | Modifier::DOUBLE_UNDERLINED,
+            to: Modifier::DOUBLE_UNDERLINED,
+        };
+        diff.queue(&mut out).unwrap();
+
+        let mut expected = Vec::new();
+        queue!(
+            expected,
+            SetAttribute(Attribute::NoUnderline),
+            SetAttribute(Attribute::DoubleUnderlined)
+        )
+        .unwrap();
+        assert_eq!(out, expected);
+    }
+}
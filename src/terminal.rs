@@ -1,29 +1,77 @@
-use std::io::{self, Write};
+use std::io::{self, Stdout, Write};
 use crossterm::style::{
     Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
 };
-use crossterm::{cursor::MoveTo, queue};
+use crossterm::{
+    cursor::{Hide, MoveDown, MoveTo, MoveToColumn, RestorePosition, SavePosition, Show},
+    event::{
+        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute, queue,
+    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
 
+use crate::backend::{Backend, CrosstermBackend};
 use crate::window::Window;
-use crate::{buffer::Buffer, text::Modifier};
+use crate::buffer::{Buffer, Cell};
+use crate::render::{self, ModifierDiff, RenderOptions};
+use crate::text::{Modifier, Style};
 
 #[derive(Debug, Clone, PartialEq, Hash)]
-pub struct Terminal {
+pub struct Terminal<B: Backend = CrosstermBackend> {
+    backend: B,
     width: u16,
     height: u16,
     buffer: Buffer,
+    options: RenderOptions,
+    /// The output of the current call, written to the backend in one go. Kept to reuse its
+    /// allocation and always left empty.
+    out: Vec<u8>,
 }
 
 impl Terminal {
     pub fn init() -> Self {
         let (width, height) = crossterm::terminal::size().expect("Unable to get terminal size");
+        Terminal::with_backend(CrosstermBackend::new(), width, height)
+    }
+
+    pub fn set_cursor_pos(x: u16, y: u16) -> io::Result<()> {
+        crossterm::execute!(io::stdout(), MoveTo(x, y))
+    }
+}
+
+impl<B: Backend> Terminal<B> {
+    pub fn with_backend(backend: B, width: u16, height: u16) -> Self {
         Terminal {
+            backend,
             width,
             height,
             buffer: Buffer::empty(width, height),
+            options: RenderOptions::default(),
+            out: Vec::new(),
         }
     }
 
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Allows `draw` to use relative cursor moves when they are shorter than an absolute `MoveTo`.
+    pub fn set_relative_moves(&mut self, enabled: bool) {
+        self.options.relative_moves = enabled;
+    }
+
+    /// Draws box drawing characters, such as window borders, and a few common symbols as ASCII
+    /// for terminals that can't display them.
+    pub fn set_ascii(&mut self, enabled: bool) {
+        self.options.ascii = enabled;
+    }
+
     pub fn resize(&mut self, width: u16, height: u16) {
         self.width = width;
         self.height = height;
@@ -31,150 +79,465 @@ impl Terminal {
         self.buffer.reset();
     }
 
-    pub fn set_cursor_pos(x: u16, y: u16) -> io::Result<()> {
-        crossterm::execute!(io::stdout(), MoveTo(x, y))
+    /// Blanks the screen and moves the cursor to the top-left corner, e.g. on startup or when
+    /// switching views, so the next draw starts from a known state.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.sync_size();
+        self.write_to_backend(|terminal, out| terminal.clear_to(out))
+    }
+
+    /// Writes the escape sequences clearing the screen for `clear` to `writer`.
+    pub fn clear_to<W>(&mut self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.buffer.reset();
+        queue!(writer, Clear(ClearType::All), MoveTo(0, 0))
+    }
+
+    /// Clears the screen to spaces in `style`, e.g. to show a themed background color rather
+    /// than the terminal's default.
+    pub fn clear_with(&mut self, style: Style) -> io::Result<()> {
+        self.write_to_backend(|terminal, out| terminal.draw_clear(out, style))
+    }
+
+    /// Writes the screen cleared by `clear_with` to `writer`.
+    pub fn draw_clear<W>(&mut self, mut writer: W, style: Style) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut cell = Cell::default();
+        cell.set_style(style);
+        self.buffer = Buffer::filled(self.width, self.height, &cell);
+
+        ModifierDiff {
+            from: Modifier::empty(),
+            to: cell.modifier,
+        }
+        .queue(&mut writer)?;
+        queue!(
+            writer,
+            SetForegroundColor(cell.fg),
+            SetBackgroundColor(cell.bg)
+        )?;
+        let row = " ".repeat(self.width as usize);
+        for y in 0..self.height {
+            queue!(writer, MoveTo(0, y), Print(&row))?;
+        }
+        queue!(
+            writer,
+            SetForegroundColor(Color::Reset),
+            SetBackgroundColor(Color::Reset),
+            SetAttribute(Attribute::Reset),
+        )
     }
 
     pub fn put(&mut self, win: &Window) -> io::Result<()> {
+        self.sync_size();
+        self.write_to_backend(|terminal, out| terminal.draw(out, win))
+    }
+
+    /// Draws `windows` as a single frame, in slice order so the last one ends up on top.
+    pub fn draw_all(&mut self, windows: &[&Window]) -> io::Result<()> {
+        self.sync_size();
+        self.write_to_backend(|terminal, out| terminal.draw_all_to(out, windows))
+    }
+
+    /// Runs `draw` with the reused output buffer and writes what it produced to the backend.
+    fn write_to_backend<F>(&mut self, draw: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut Self, &mut Vec<u8>) -> io::Result<()>,
+    {
+        let mut out = std::mem::take(&mut self.out);
+        let result = draw(self, &mut out).and_then(|()| {
+            let writer = self.backend.writer();
+            writer.write_all(&out)?;
+            writer.flush()
+        });
+        out.clear();
+        self.out = out;
+        result
+    }
+
+    /// Writes the frame drawn by `draw_all` to `writer`. Parts of the screen not covered by any
+    /// window are cleared, and cells with `skip` set leave the windows below them visible. The
+    /// windows are composited with `Window::draw` like in `WindowManager`, so translucent cells
+    /// blend with the windows below them.
+    pub fn draw_all_to<W>(&mut self, mut writer: W, windows: &[&Window]) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut frame = Buffer::empty(self.width, self.height);
+        for win in windows {
+            win.draw(&mut frame);
+        }
+
+        queue!(writer, SavePosition)?;
+        render::render_cells(&mut writer, self.buffer.diff(&frame), self.options)?;
+        queue!(writer, RestorePosition)?;
+        self.buffer = frame;
+        Ok(())
+    }
+
+    /// Follows the terminal size, keeping what is known to be on the screen. Backends other
+    /// than a tty have no size to follow.
+    fn sync_size(&mut self) {
+        if !self.backend.is_tty() {
+            return;
+        }
         let (width, height) = crossterm::terminal::size().expect("Unable to get terminal size");
         if width != self.width || height != self.height {
             self.buffer.resize(width, height);
             self.width = width;
             self.height = height;
         }
-        self.draw(io::stdout(), win)?;
-        io::stdout().flush()?;
-        Ok(())
     }
 
-    pub fn draw<'a, W>(&mut self, mut writer: W, win: &Window) -> io::Result<()>
+    pub fn draw<W>(&mut self, mut writer: W, win: &Window) -> io::Result<()>
     where
         W: Write,
     {
         let (win_x, win_y) = win.pos();
 
-        let cursor = crossterm::cursor::position().expect("Unable to get cursor position");
+        let options = self.options;
+        let buffer = &mut self.buffer;
+        let cells = win.content_iter().filter_map(|(x, y, cell)| {
+            let (x, y) = (x + win_x, y + win_y);
+            let index = buffer.index_of(x, y);
+            if *cell == buffer.content[index] {
+                return None;
+            }
+            buffer.content[index] = cell.clone();
+            Some((x, y, cell))
+        });
+        queue!(writer, SavePosition)?;
+        render::render_cells(&mut writer, cells, options)?;
+        queue!(writer, RestorePosition)
+    }
+}
 
-        let mut fg = Color::Reset;
-        let mut bg = Color::Reset;
-        let mut modifier = Modifier::empty();
-        let mut last_pos: Option<(u16, u16)> = None;
-        for (x, y, cell) in win.content_iter() {
-            let x = x+win_x;
-            let y = y+win_y;
+/// Switches the terminal to the alternate screen with a hidden cursor, mouse capture and raw
+/// mode for as long as it is alive, restoring everything when dropped, including while unwinding
+/// from a panic. Mouse capture is what makes the terminal report the mouse events
+/// `WindowManager` routes to windows.
+///
+/// Where the terminal supports it, keyboard enhancement is enabled as well, so key repeats and
+/// releases are reported as such.
+///
+/// Terminals without an alternate screen get the main screen cleared instead. On exit, the last
+/// frame is left on the screen like the output of any other command, with the cursor on a new
+/// line below it.
+#[derive(Debug)]
+pub struct ScreenGuard<W: Write = Stdout> {
+    writer: W,
+    raw_mode: bool,
+    alternate_screen: bool,
+    keyboard_enhancement: bool,
+}
 
-            let index = self.buffer.index_of(x, y);
+impl ScreenGuard {
+    pub fn new() -> io::Result<Self> {
+        let alternate_screen = supports_alternate_screen();
+        let mut guard = ScreenGuard::with_options(io::stdout(), alternate_screen)?;
+        crossterm::terminal::enable_raw_mode()?;
+        guard.raw_mode = true;
+        // Querying the terminal for support needs raw mode.
+        if crossterm::terminal::supports_keyboard_enhancement().unwrap_or(false) {
+            execute!(
+                guard.writer,
+                PushKeyboardEnhancementFlags(
+                    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                )
+            )?;
+            guard.keyboard_enhancement = true;
+        }
+        Ok(guard)
+    }
+}
 
-            if *cell == self.buffer.content[index] {
-                continue;
-            }
+impl<W: Write> ScreenGuard<W> {
+    /// Enters the alternate screen, captures the mouse and hides the cursor on `writer`. Raw
+    /// mode belongs to the controlling terminal rather than a writer, so it is left untouched.
+    pub fn with_writer(writer: W) -> io::Result<Self> {
+        ScreenGuard::with_options(writer, true)
+    }
 
-            self.buffer.content[index] = cell.clone();
+    /// Like [`ScreenGuard::with_writer`], but clears the main screen instead of entering the
+    /// alternate screen when `alternate_screen` is `false`.
+    pub fn with_options(mut writer: W, alternate_screen: bool) -> io::Result<Self> {
+        if alternate_screen {
+            execute!(writer, EnterAlternateScreen, EnableMouseCapture, Hide)?;
+        } else {
+            execute!(
+                writer,
+                Clear(ClearType::All),
+                MoveTo(0, 0),
+                EnableMouseCapture,
+                Hide
+            )?;
+        }
+        Ok(ScreenGuard {
+            writer,
+            raw_mode: false,
+            alternate_screen,
+            keyboard_enhancement: false,
+        })
+    }
 
-            // Move the cursor if the previous location was not (x - 1, y)
-            if !matches!(last_pos, Some(p) if x == p.0 + 1 && y == p.1) {
-                queue!(writer, MoveTo(x, y))?;
-            }
-            last_pos = Some((x, y));
-            if cell.modifier != modifier {
-                let diff = ModifierDiff {
-                    from: modifier,
-                    to: cell.modifier,
-                };
-                diff.queue(&mut writer)?;
-                modifier = cell.modifier;
+    pub fn alternate_screen(&self) -> bool {
+        self.alternate_screen
+    }
+}
+
+impl<W: Write> Drop for ScreenGuard<W> {
+    fn drop(&mut self) {
+        // Errors can't be reported from `drop`, restore as much as possible regardless.
+        if self.keyboard_enhancement {
+            let _ = execute!(self.writer, PopKeyboardEnhancementFlags);
+        }
+        if self.raw_mode {
+            let _ = crossterm::terminal::disable_raw_mode();
+        }
+        let _ = if self.alternate_screen {
+            execute!(self.writer, DisableMouseCapture, LeaveAlternateScreen, Show)
+        } else {
+            // Cursor moves stop at the bottom row, so this ends on a new line below the frame.
+            execute!(
+                self.writer,
+                DisableMouseCapture,
+                MoveToColumn(0),
+                MoveDown(u16::MAX),
+                Print("\r\n"),
+                Show
+            )
+        };
+    }
+}
+
+/// Best-effort check whether the terminal has an alternate screen, based on `TERM`. Dumb
+/// terminals and an unset `TERM` (outside of Windows) are assumed not to.
+pub fn supports_alternate_screen() -> bool {
+    if cfg!(windows) {
+        return true;
+    }
+    match std::env::var("TERM") {
+        Ok(term) => !term.is_empty() && term != "dumb",
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widget::Widget;
+    use crate::window::{Event, Rect};
+
+    #[test]
+    fn screen_guard_restores_on_drop() {
+        let mut out = Vec::new();
+        let guard = ScreenGuard::with_writer(&mut out).unwrap();
+        drop(guard);
+
+        let mut expected = Vec::new();
+        execute!(
+            expected,
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            Hide,
+            DisableMouseCapture,
+            LeaveAlternateScreen,
+            Show
+        )
+        .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn screen_guard_falls_back_without_alternate_screen() {
+        let mut out = Vec::new();
+        let guard = ScreenGuard::with_options(&mut out, false).unwrap();
+        assert!(!guard.alternate_screen());
+        drop(guard);
+
+        let mut expected = Vec::new();
+        execute!(
+            expected,
+            Clear(ClearType::All),
+            MoveTo(0, 0),
+            EnableMouseCapture,
+            Hide,
+            DisableMouseCapture,
+            MoveToColumn(0),
+            MoveDown(u16::MAX),
+            Print("\r\n"),
+            Show
+        )
+        .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn draw_all_puts_last_window_on_top() {
+        let mut terminal = Terminal::with_backend(Vec::new(), 8, 3);
+        let mut bottom = Window::new(Rect::new(0, 0, 5, 2));
+        bottom.set_lines(0, 0, "aaaaa\naaaaa", Style::default());
+        let mut top = Window::new(Rect::new(3, 1, 4, 2));
+        top.set_lines(0, 0, "bbbb\nbbbb", Style::default());
+
+        let mut out = Vec::new();
+        terminal.draw_all_to(&mut out, &[&bottom, &top]).unwrap();
+
+        assert_eq!(
+            terminal.buffer.to_lines(),
+            vec!["aaaaa   ", "aaabbbb ", "   bbbb "]
+        );
+        assert!(String::from_utf8(out).unwrap().contains("bbbb"));
+
+        // Redrawing the same windows leaves nothing to write but the cursor save and restore.
+        let mut out = Vec::new();
+        terminal.draw_all_to(&mut out, &[&bottom, &top]).unwrap();
+        let mut expected = Vec::new();
+        queue!(expected, SavePosition).unwrap();
+        render::render_cells(&mut expected, std::iter::empty(), RenderOptions::default()).unwrap();
+        queue!(expected, RestorePosition).unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn draw_all_blends_translucent_cells() {
+        const BLUE: Color = Color::Rgb { r: 0, g: 0, b: 200 };
+        const RED: Color = Color::Rgb { r: 200, g: 0, b: 0 };
+        const PURPLE: Color = Color::Rgb {
+            r: 100,
+            g: 0,
+            b: 100,
+        };
+
+        #[derive(Debug)]
+        struct Tint;
+
+        impl Widget for Tint {
+            fn render(&self, buf: &mut Buffer, area: Rect) {
+                buf.set_style(area, Style::default().bg(RED));
+                for cell in &mut buf.content {
+                    cell.set_alpha(128);
+                }
             }
-            if cell.fg != fg {
-                let color = cell.fg;
-                queue!(writer, SetForegroundColor(color))?;
-                fg = cell.fg;
+
+            fn on_event(&mut self, _: Event) -> bool {
+                false
             }
-            if cell.bg != bg {
-                let color = cell.bg;
-                queue!(writer, SetBackgroundColor(color))?;
-                bg = cell.bg;
+        }
+
+        let mut terminal = Terminal::with_backend(Vec::new(), 4, 1);
+        let mut bottom = Window::new(Rect::new(0, 0, 4, 1));
+        bottom.set_lines(0, 0, "abcd", Style::default().bg(BLUE));
+        let mut top = Window::new(Rect::new(2, 0, 2, 1));
+        top.set_widget(Tint);
+        top.render_widget();
+
+        terminal.draw_all_to(Vec::new(), &[&bottom, &top]).unwrap();
+        assert_eq!(terminal.buffer[(3, 0)].symbol, " ");
+        assert_eq!(terminal.buffer[(3, 0)].bg, PURPLE);
+        assert_eq!(terminal.buffer[(1, 0)].bg, BLUE);
+    }
+
+    #[test]
+    fn draw_all_shows_windows_below_skip_cells() {
+        #[derive(Debug)]
+        struct Hole;
+
+        impl Widget for Hole {
+            fn render(&self, buf: &mut Buffer, area: Rect) {
+                buf.set_string(area.x, area.y, "xy", Style::default());
+                buf[(area.x, area.y)].set_skip(true);
             }
 
-            queue!(writer, Print(&cell.symbol))?;
+            fn on_event(&mut self, _: Event) -> bool {
+                false
+            }
         }
 
+        let mut terminal = Terminal::with_backend(Vec::new(), 4, 1);
+        let mut bottom = Window::new(Rect::new(0, 0, 4, 1));
+        bottom.set_lines(0, 0, "abcd", Style::default());
+        let mut top = Window::new(Rect::new(2, 0, 2, 1));
+        top.set_widget(Hole);
+        top.render_widget();
+
+        terminal.draw_all_to(Vec::new(), &[&bottom, &top]).unwrap();
+        assert_eq!(terminal.buffer[(2, 0)].symbol, "c");
+        assert!(!terminal.buffer[(2, 0)].skip);
+        assert_eq!(terminal.buffer[(3, 0)].symbol, "y");
+    }
+
+    #[test]
+    fn clear_with_fills_style() {
+        let mut terminal = Terminal::with_backend(Vec::new(), 4, 2);
+        let mut out = Vec::new();
+        terminal
+            .draw_clear(&mut out, Style::default().bg(Color::Blue))
+            .unwrap();
+
+        assert!(terminal
+            .buffer
+            .content
+            .iter()
+            .all(|cell| cell.symbol == " " && cell.bg == Color::Blue));
+        let mut expected = Vec::new();
         queue!(
-            writer,
+            expected,
             SetForegroundColor(Color::Reset),
-            SetBackgroundColor(Color::Reset),
-            SetAttribute(Attribute::Reset),
-            MoveTo(cursor.0, cursor.1),
+            SetBackgroundColor(Color::Blue),
+            MoveTo(0, 0),
+            Print("    "),
+            MoveTo(0, 1),
+            Print("    "),
         )
+        .unwrap();
+        assert!(out.starts_with(&expected));
     }
-}
 
-/// The `ModifierDiff` struct is used to calculate the difference between two `Modifier`
-/// values. This is useful when updating the terminal display, as it allows for more
-/// efficient updates by only sending the necessary changes.
-#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
-struct ModifierDiff {
-    pub from: Modifier,
-    pub to: Modifier,
-}
+    #[test]
+    fn draws_to_backend() {
+        let mut terminal = Terminal::with_backend(Vec::new(), 4, 2);
+        let mut win = Window::new(Rect::new(1, 1, 2, 1));
+        win.set_lines(0, 0, "ab", Style::default());
+        terminal.put(&win).unwrap();
 
-impl ModifierDiff {
-    fn queue<W>(&self, mut w: W) -> io::Result<()>
-    where
-        W: io::Write,
-    {
-        //use crossterm::Attribute;
-        let removed = self.from - self.to;
-        if removed.contains(Modifier::REVERSED) {
-            queue!(w, SetAttribute(Attribute::NoReverse))?;
-        }
-        if removed.contains(Modifier::BOLD) {
-            queue!(w, SetAttribute(Attribute::NormalIntensity))?;
-            if self.to.contains(Modifier::DIM) {
-                queue!(w, SetAttribute(Attribute::Dim))?;
-            }
-        }
-        if removed.contains(Modifier::ITALIC) {
-            queue!(w, SetAttribute(Attribute::NoItalic))?;
-        }
-        if removed.contains(Modifier::UNDERLINED) {
-            queue!(w, SetAttribute(Attribute::NoUnderline))?;
-        }
-        if removed.contains(Modifier::DIM) {
-            queue!(w, SetAttribute(Attribute::NormalIntensity))?;
-        }
-        if removed.contains(Modifier::CROSSED_OUT) {
-            queue!(w, SetAttribute(Attribute::NotCrossedOut))?;
-        }
-        if removed.contains(Modifier::SLOW_BLINK) || removed.contains(Modifier::RAPID_BLINK) {
-            queue!(w, SetAttribute(Attribute::NoBlink))?;
-        }
+        let mut expected = Vec::new();
+        queue!(expected, SavePosition).unwrap();
+        render::render_cells(
+            &mut expected,
+            win.content_iter().map(|(x, y, cell)| (x + 1, y + 1, cell)),
+            RenderOptions::default(),
+        )
+        .unwrap();
+        queue!(expected, RestorePosition).unwrap();
+        assert_eq!(terminal.backend(), &expected);
+        assert!(terminal.out.is_empty());
 
-        let added = self.to - self.from;
-        if added.contains(Modifier::REVERSED) {
-            queue!(w, SetAttribute(Attribute::Reverse))?;
-        }
-        if added.contains(Modifier::BOLD) {
-            queue!(w, SetAttribute(Attribute::Bold))?;
-        }
-        if added.contains(Modifier::ITALIC) {
-            queue!(w, SetAttribute(Attribute::Italic))?;
-        }
-        if added.contains(Modifier::UNDERLINED) {
-            queue!(w, SetAttribute(Attribute::Underlined))?;
-        }
-        if added.contains(Modifier::DIM) {
-            queue!(w, SetAttribute(Attribute::Dim))?;
-        }
-        if added.contains(Modifier::CROSSED_OUT) {
-            queue!(w, SetAttribute(Attribute::CrossedOut))?;
-        }
-        if added.contains(Modifier::SLOW_BLINK) {
-            queue!(w, SetAttribute(Attribute::SlowBlink))?;
-        }
-        if added.contains(Modifier::RAPID_BLINK) {
-            queue!(w, SetAttribute(Attribute::RapidBlink))?;
-        }
+        terminal.backend_mut().clear();
+        terminal.clear().unwrap();
+        let mut expected = Vec::new();
+        queue!(expected, Clear(ClearType::All), MoveTo(0, 0)).unwrap();
+        assert_eq!(terminal.backend(), &expected);
+    }
 
-        Ok(())
+    #[test]
+    fn clear_resets_buffer() {
+        let mut terminal = Terminal::with_backend(Vec::new(), 4, 2);
+        terminal
+            .buffer
+            .set_string(0, 0, "abcd", Style::default().bg(Color::Red));
+
+        let mut out = Vec::new();
+        terminal.clear_to(&mut out).unwrap();
+
+        let mut expected = Vec::new();
+        queue!(expected, Clear(ClearType::All), MoveTo(0, 0)).unwrap();
+        assert_eq!(out, expected);
+        assert_eq!(terminal.buffer, Buffer::empty(4, 2));
     }
 }
@@ -1,11 +1,18 @@
 use bitflags::bitflags;
-use crossterm::style::Color;
+pub use crossterm::style::Color;
+use crossterm::style::{Attribute, Attributes, ContentStyle};
 use std::fmt;
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Style {
+    #[cfg_attr(feature = "serde", serde(with = "serde_color::option"))]
     pub fg: Option<Color>,
+    #[cfg_attr(feature = "serde", serde(with = "serde_color::option"))]
     pub bg: Option<Color>,
+    #[cfg_attr(feature = "serde", serde(with = "serde_color::option"))]
+    pub underline_color: Option<Color>,
     pub add_modifier: Modifier,
     pub sub_modifier: Modifier,
 }
@@ -21,6 +28,7 @@ impl Style {
         Style {
             fg: None,
             bg: None,
+            underline_color: None,
             add_modifier: Modifier::empty(),
             sub_modifier: Modifier::empty(),
         }
@@ -31,6 +39,7 @@ impl Style {
         Style {
             fg: Some(Color::Reset),
             bg: Some(Color::Reset),
+            underline_color: Some(Color::Reset),
             add_modifier: Modifier::empty(),
             sub_modifier: Modifier::all(),
         }
@@ -41,7 +50,8 @@ impl Style {
     /// ## Examples
     ///
     /// ```rust
-    /// # use ratatui::prelude::*;
+    /// # use crossterm_window::text::{Modifier, Style};
+    /// # use crossterm::style::Color;
     /// let style = Style::default().fg(Color::Blue);
     /// let diff = Style::default().fg(Color::Red);
     /// assert_eq!(style.patch(diff), Style::default().fg(Color::Red));
@@ -56,7 +66,8 @@ impl Style {
     /// ## Examples
     ///
     /// ```rust
-    /// # use ratatui::prelude::*;
+    /// # use crossterm_window::text::{Modifier, Style};
+    /// # use crossterm::style::Color;
     /// let style = Style::default().bg(Color::Blue);
     /// let diff = Style::default().bg(Color::Red);
     /// assert_eq!(style.patch(diff), Style::default().bg(Color::Red));
@@ -66,6 +77,60 @@ impl Style {
         self
     }
 
+    /// Changes the foreground color to a 24-bit RGB color.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// # use crossterm_window::text::Style;
+    /// # use crossterm::style::Color;
+    /// let style = Style::default().fg_rgb(10, 20, 30);
+    /// assert_eq!(style.fg, Some(Color::Rgb { r: 10, g: 20, b: 30 }));
+    /// ```
+    pub const fn fg_rgb(self, r: u8, g: u8, b: u8) -> Style {
+        self.fg(Color::Rgb { r, g, b })
+    }
+
+    /// Changes the background color to a 24-bit RGB color.
+    pub const fn bg_rgb(self, r: u8, g: u8, b: u8) -> Style {
+        self.bg(Color::Rgb { r, g, b })
+    }
+
+    /// Changes the foreground color to one of the 256 indexed colors.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// # use crossterm_window::text::Style;
+    /// # use crossterm::style::Color;
+    /// let style = Style::default().fg_indexed(208);
+    /// assert_eq!(style.fg, Some(Color::AnsiValue(208)));
+    /// ```
+    pub const fn fg_indexed(self, index: u8) -> Style {
+        self.fg(Color::AnsiValue(index))
+    }
+
+    /// Changes the background color to one of the 256 indexed colors.
+    pub const fn bg_indexed(self, index: u8) -> Style {
+        self.bg(Color::AnsiValue(index))
+    }
+
+    /// Changes the color of underlines, which terminals otherwise draw in the foreground color.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// # use crossterm_window::text::{Modifier, Style};
+    /// # use crossterm::style::Color;
+    /// let style = Style::default().underline_color(Color::Blue);
+    /// let diff = Style::default().underline_color(Color::Red);
+    /// assert_eq!(style.patch(diff), Style::default().underline_color(Color::Red));
+    /// ```
+    pub const fn underline_color(mut self, color: Color) -> Style {
+        self.underline_color = Some(color);
+        self
+    }
+
     /// Changes the text emphasis.
     ///
     /// When applied, it adds the given modifier to the `Style` modifiers.
@@ -73,7 +138,8 @@ impl Style {
     /// ## Examples
     ///
     /// ```rust
-    /// # use ratatui::prelude::*;
+    /// # use crossterm_window::text::{Modifier, Style};
+    /// # use crossterm::style::Color;
     /// let style = Style::default().add_modifier(Modifier::BOLD);
     /// let diff = Style::default().add_modifier(Modifier::ITALIC);
     /// let patched = style.patch(diff);
@@ -93,7 +159,8 @@ impl Style {
     /// ## Examples
     ///
     /// ```rust
-    /// # use ratatui::prelude::*;
+    /// # use crossterm_window::text::{Modifier, Style};
+    /// # use crossterm::style::Color;
     /// let style = Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC);
     /// let diff = Style::default().remove_modifier(Modifier::ITALIC);
     /// let patched = style.patch(diff);
@@ -111,7 +178,8 @@ impl Style {
     ///
     /// ## Examples
     /// ```
-    /// # use ratatui::prelude::*;
+    /// # use crossterm_window::text::{Modifier, Style};
+    /// # use crossterm::style::Color;
     /// let style_1 = Style::default().fg(Color::Yellow);
     /// let style_2 = Style::default().bg(Color::Red);
     /// let combined = style_1.patch(style_2);
@@ -122,6 +190,7 @@ impl Style {
     pub fn patch(mut self, other: Style) -> Style {
         self.fg = other.fg.or(self.fg);
         self.bg = other.bg.or(self.bg);
+        self.underline_color = other.underline_color.or(self.underline_color);
 
         self.add_modifier.remove(other.sub_modifier);
         self.add_modifier.insert(other.add_modifier);
@@ -134,6 +203,7 @@ impl Style {
 
 bitflags! {
     #[derive(Default, Clone, Copy, Eq, PartialEq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Modifier: u16 {
         const BOLD              = 0b0000_0000_0001;
         const DIM               = 0b0000_0000_0010;
@@ -144,6 +214,70 @@ bitflags! {
         const REVERSED          = 0b0000_0100_0000;
         const HIDDEN            = 0b0000_1000_0000;
         const CROSSED_OUT       = 0b0001_0000_0000;
+        const OVERLINED         = 0b0010_0000_0000;
+        const DOUBLE_UNDERLINED = 0b0100_0000_0000;
+    }
+}
+
+impl Modifier {
+    /// The crossterm attribute enabling each modifier.
+    const ATTRIBUTES: [(Modifier, Attribute); 11] = [
+        (Modifier::BOLD, Attribute::Bold),
+        (Modifier::DIM, Attribute::Dim),
+        (Modifier::ITALIC, Attribute::Italic),
+        (Modifier::UNDERLINED, Attribute::Underlined),
+        (Modifier::SLOW_BLINK, Attribute::SlowBlink),
+        (Modifier::RAPID_BLINK, Attribute::RapidBlink),
+        (Modifier::REVERSED, Attribute::Reverse),
+        (Modifier::HIDDEN, Attribute::Hidden),
+        (Modifier::CROSSED_OUT, Attribute::CrossedOut),
+        (Modifier::OVERLINED, Attribute::OverLined),
+        (Modifier::DOUBLE_UNDERLINED, Attribute::DoubleUnderlined),
+    ];
+}
+
+impl From<Attributes> for Modifier {
+    fn from(attributes: Attributes) -> Modifier {
+        Modifier::ATTRIBUTES
+            .iter()
+            .filter(|(_, attribute)| attributes.has(*attribute))
+            .fold(Modifier::empty(), |acc, (modifier, _)| acc | *modifier)
+    }
+}
+
+impl From<Modifier> for Attributes {
+    fn from(modifier: Modifier) -> Attributes {
+        Modifier::ATTRIBUTES
+            .iter()
+            .filter(|(m, _)| modifier.contains(*m))
+            .fold(Attributes::default(), |acc, (_, attribute)| {
+                acc | *attribute
+            })
+    }
+}
+
+impl From<ContentStyle> for Style {
+    fn from(style: ContentStyle) -> Style {
+        Style {
+            fg: style.foreground_color,
+            bg: style.background_color,
+            underline_color: style.underline_color,
+            add_modifier: style.attributes.into(),
+            sub_modifier: Modifier::empty(),
+        }
+    }
+}
+
+/// Converts the colors and added modifiers. `ContentStyle` describes a complete style rather
+/// than a change to one, so the removed modifiers are dropped.
+impl From<Style> for ContentStyle {
+    fn from(style: Style) -> ContentStyle {
+        ContentStyle {
+            foreground_color: style.fg,
+            background_color: style.bg,
+            underline_color: style.underline_color,
+            attributes: style.add_modifier.into(),
+        }
     }
 }
 
@@ -157,3 +291,436 @@ impl fmt::Debug for Modifier {
         fmt::Debug::fmt(&self.0, f)
     }
 }
+
+/// Builds a [`Style`] from a list of colors and modifiers.
+///
+/// `fg:` and `bg:` take either a [`Color`] variant name or a string understood by
+/// [`parse_color`], and any other entry is a modifier in lower case.
+///
+/// # Panics
+///
+/// Panics if a color string can't be parsed.
+///
+/// # Examples
+///
+/// ```
+/// # use crossterm_window::style;
+/// # use crossterm_window::text::{Color, Modifier, Style};
+/// assert_eq!(
+///     style!(fg: Red, bg: "#0000ff", bold, italic),
+///     Style::default()
+///         .fg(Color::Red)
+///         .bg(Color::Rgb { r: 0, g: 0, b: 255 })
+///         .add_modifier(Modifier::BOLD | Modifier::ITALIC),
+/// );
+/// ```
+#[macro_export]
+macro_rules! style {
+    (@apply $style:expr;) => { $style };
+    (@apply $style:expr; fg: $color:tt $(, $($rest:tt)*)?) => {
+        $crate::style!(@apply $style.fg($crate::style!(@color $color)); $($($rest)*)?)
+    };
+    (@apply $style:expr; bg: $color:tt $(, $($rest:tt)*)?) => {
+        $crate::style!(@apply $style.bg($crate::style!(@color $color)); $($($rest)*)?)
+    };
+    (@apply $style:expr; $modifier:ident $(, $($rest:tt)*)?) => {
+        $crate::style!(
+            @apply $style.add_modifier($crate::style!(@modifier $modifier)); $($($rest)*)?
+        )
+    };
+    (@color $color:ident) => { $crate::text::Color::$color };
+    (@color $color:literal) => {
+        match $crate::text::parse_color($color) {
+            Some(color) => color,
+            None => panic!("invalid color: {:?}", $color),
+        }
+    };
+    (@modifier bold) => { $crate::text::Modifier::BOLD };
+    (@modifier dim) => { $crate::text::Modifier::DIM };
+    (@modifier italic) => { $crate::text::Modifier::ITALIC };
+    (@modifier underlined) => { $crate::text::Modifier::UNDERLINED };
+    (@modifier slow_blink) => { $crate::text::Modifier::SLOW_BLINK };
+    (@modifier rapid_blink) => { $crate::text::Modifier::RAPID_BLINK };
+    (@modifier reversed) => { $crate::text::Modifier::REVERSED };
+    (@modifier hidden) => { $crate::text::Modifier::HIDDEN };
+    (@modifier crossed_out) => { $crate::text::Modifier::CROSSED_OUT };
+    (@modifier overlined) => { $crate::text::Modifier::OVERLINED };
+    (@modifier double_underlined) => { $crate::text::Modifier::DOUBLE_UNDERLINED };
+    ($($entries:tt)*) => { $crate::style!(@apply $crate::text::Style::new(); $($entries)*) };
+}
+
+/// Parses a color given as `#rrggbb`, `#rgb` or a name such as `dark_red`.
+///
+/// ## Examples
+///
+/// ```rust
+/// # use crossterm_window::text::{parse_color, Color};
+/// assert_eq!(parse_color("#ff8000"), Some(Color::Rgb { r: 255, g: 128, b: 0 }));
+/// assert_eq!(parse_color("#f80"), Some(Color::Rgb { r: 255, g: 136, b: 0 }));
+/// assert_eq!(parse_color("Dark_Red"), Some(Color::DarkRed));
+/// assert_eq!(parse_color("#ff80"), None);
+/// ```
+pub fn parse_color(s: &str) -> Option<Color> {
+    let Some(hex) = s.strip_prefix('#') else {
+        return Color::try_from(s).ok();
+    };
+    if !hex.is_ascii() {
+        return None;
+    }
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    match hex.len() {
+        6 => Some(Color::Rgb {
+            r: channel(&hex[0..2])?,
+            g: channel(&hex[2..4])?,
+            b: channel(&hex[4..6])?,
+        }),
+        3 => Some(Color::Rgb {
+            r: channel(&hex[0..1])? * 17,
+            g: channel(&hex[1..2])? * 17,
+            b: channel(&hex[2..3])? * 17,
+        }),
+        _ => None,
+    }
+}
+
+/// Horizontal placement of text within the space available to it.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// A piece of text in a single style.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub content: String,
+    pub style: Style,
+}
+
+impl Span {
+    /// A span in the default style.
+    pub fn raw<S: Into<String>>(content: S) -> Span {
+        Span::styled(content, Style::default())
+    }
+
+    pub fn styled<S: Into<String>>(content: S, style: Style) -> Span {
+        Span {
+            content: content.into(),
+            style,
+        }
+    }
+
+    /// The number of columns the span takes up.
+    pub fn width(&self) -> usize {
+        self.content.width()
+    }
+}
+
+impl From<&str> for Span {
+    fn from(content: &str) -> Span {
+        Span::raw(content)
+    }
+}
+
+impl From<String> for Span {
+    fn from(content: String) -> Span {
+        Span::raw(content)
+    }
+}
+
+/// A single line of text made of spans in different styles, drawn one after the other.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Line(pub Vec<Span>);
+
+impl Line {
+    /// The number of columns the line takes up.
+    pub fn width(&self) -> usize {
+        self.0.iter().map(Span::width).sum()
+    }
+}
+
+impl From<Vec<Span>> for Line {
+    fn from(spans: Vec<Span>) -> Line {
+        Line(spans)
+    }
+}
+
+impl From<Span> for Line {
+    fn from(span: Span) -> Line {
+        Line(vec![span])
+    }
+}
+
+impl From<&str> for Line {
+    fn from(content: &str) -> Line {
+        Line(vec![Span::raw(content)])
+    }
+}
+
+/// Linearly interpolates between two colors, `t = 0.0` giving `from` and `t = 1.0` giving `to`.
+///
+/// Named and indexed colors are blended using their usual xterm RGB values. Colors without a
+/// fixed value (`Color::Reset`) can't be blended, so these switch over at `t = 0.5` instead.
+pub fn blend(from: Color, to: Color, t: f32) -> Color {
+    if t <= 0.0 || from == to {
+        return from;
+    }
+    if t >= 1.0 {
+        return to;
+    }
+    match (to_rgb(from), to_rgb(to)) {
+        (Some(a), Some(b)) => {
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+            Color::Rgb {
+                r: mix(a.0, b.0),
+                g: mix(a.1, b.1),
+                b: mix(a.2, b.2),
+            }
+        }
+        _ if t < 0.5 => from,
+        _ => to,
+    }
+}
+
+fn to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (128, 0, 0),
+        (0, 128, 0),
+        (128, 128, 0),
+        (0, 0, 128),
+        (128, 0, 128),
+        (0, 128, 128),
+        (192, 192, 192),
+        (128, 128, 128),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (0, 0, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+
+    let index = match color {
+        Color::Reset => return None,
+        Color::Rgb { r, g, b } => return Some((r, g, b)),
+        Color::Black => 0,
+        Color::DarkRed => 1,
+        Color::DarkGreen => 2,
+        Color::DarkYellow => 3,
+        Color::DarkBlue => 4,
+        Color::DarkMagenta => 5,
+        Color::DarkCyan => 6,
+        Color::Grey => 7,
+        Color::DarkGrey => 8,
+        Color::Red => 9,
+        Color::Green => 10,
+        Color::Yellow => 11,
+        Color::Blue => 12,
+        Color::Magenta => 13,
+        Color::Cyan => 14,
+        Color::White => 15,
+        Color::AnsiValue(i) => i,
+    };
+
+    Some(match index {
+        0..=15 => ANSI[index as usize],
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = index - 16;
+            (level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        _ => {
+            let v = 8 + (index - 232) * 10;
+            (v, v, v)
+        }
+    })
+}
+
+/// Serde support for `Color` fields. crossterm can't serialize `Color::Reset`, which is in
+/// nearly every cell, so it is written as `"reset"` here and other colors are left to crossterm.
+#[cfg(feature = "serde")]
+pub(crate) mod serde_color {
+    use super::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    struct Repr(Color);
+
+    impl Serialize for Repr {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self.0 {
+                Color::Reset => serializer.serialize_str("reset"),
+                color => color.serialize(serializer),
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Repr {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let name = String::deserialize(deserializer)?;
+            if name == "reset" {
+                return Ok(Repr(Color::Reset));
+            }
+            let name = serde::de::IntoDeserializer::<D::Error>::into_deserializer(name);
+            Color::deserialize(name).map(Repr)
+        }
+    }
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        Repr(*color).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        Repr::deserialize(deserializer).map(|repr| repr.0)
+    }
+
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S>(color: &Option<Color>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            color.map(Repr).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Color>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Option::<Repr>::deserialize(deserializer).map(|color| color.map(|repr| repr.0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blend_rgb() {
+        let a = Color::Rgb {
+            r: 0,
+            g: 100,
+            b: 200,
+        };
+        let b = Color::Rgb {
+            r: 100,
+            g: 100,
+            b: 0,
+        };
+        assert_eq!(blend(a, b, 0.0), a);
+        assert_eq!(blend(a, b, 1.0), b);
+        assert_eq!(
+            blend(a, b, 0.5),
+            Color::Rgb {
+                r: 50,
+                g: 100,
+                b: 100
+            }
+        );
+    }
+
+    #[test]
+    fn blend_named_and_indexed() {
+        let gray = Color::Rgb {
+            r: 128,
+            g: 128,
+            b: 128,
+        };
+        assert_eq!(blend(Color::Black, Color::White, 0.5), gray);
+        // Cube corner 16 is black, 231 is white
+        assert_eq!(
+            blend(Color::AnsiValue(16), Color::AnsiValue(231), 0.5),
+            gray
+        );
+        // Grayscale ramp 232 is (8, 8, 8)
+        let dark = Color::Rgb { r: 4, g: 4, b: 4 };
+        assert_eq!(blend(Color::AnsiValue(232), Color::Black, 0.5), dark);
+    }
+
+    #[test]
+    fn blend_reset() {
+        assert_eq!(blend(Color::Reset, Color::Red, 0.4), Color::Reset);
+        assert_eq!(blend(Color::Reset, Color::Red, 0.6), Color::Red);
+    }
+
+    #[test]
+    fn content_style_round_trip() {
+        let content = ContentStyle {
+            foreground_color: Some(Color::Red),
+            background_color: Some(Color::Rgb { r: 1, g: 2, b: 3 }),
+            underline_color: Some(Color::Green),
+            attributes: Modifier::ATTRIBUTES
+                .iter()
+                .fold(Attributes::default(), |acc, (_, a)| acc | *a),
+        };
+
+        let style = Style::from(content);
+        assert_eq!(style.fg, Some(Color::Red));
+        assert_eq!(style.add_modifier, Modifier::all());
+        assert_eq!(ContentStyle::from(style), content);
+    }
+
+    #[test]
+    fn style_round_trip() {
+        let style = Style::default()
+            .bg(Color::Blue)
+            .add_modifier(Modifier::ITALIC | Modifier::REVERSED | Modifier::SLOW_BLINK);
+        assert_eq!(Style::from(ContentStyle::from(style)), style);
+        assert_eq!(Style::from(ContentStyle::default()), Style::default());
+    }
+
+    #[test]
+    fn style_macro() {
+        assert_eq!(style!(), Style::default());
+        assert_eq!(style!(fg: Red), Style::default().fg(Color::Red));
+        assert_eq!(
+            style!(bg: DarkBlue, underlined,),
+            Style::default()
+                .bg(Color::DarkBlue)
+                .add_modifier(Modifier::UNDERLINED)
+        );
+        assert_eq!(
+            style!(bold, fg: "#102030", crossed_out, bg: "grey"),
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .fg(Color::Rgb {
+                    r: 0x10,
+                    g: 0x20,
+                    b: 0x30
+                })
+                .add_modifier(Modifier::CROSSED_OUT)
+                .bg(Color::Grey)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid color")]
+    fn style_macro_invalid_color() {
+        let _ = style!(fg: "#12345");
+    }
+
+    #[test]
+    fn color_helpers() {
+        let style = Style::default().fg_rgb(10, 20, 30).bg_indexed(17);
+        assert_eq!(
+            style.fg,
+            Some(Color::Rgb {
+                r: 10,
+                g: 20,
+                b: 30
+            })
+        );
+        assert_eq!(style.bg, Some(Color::AnsiValue(17)));
+
+        let style = style.fg_indexed(3).bg_rgb(1, 2, 3);
+        assert_eq!(style.fg, Some(Color::AnsiValue(3)));
+        assert_eq!(style.bg, Some(Color::Rgb { r: 1, g: 2, b: 3 }));
+    }
+}
@@ -0,0 +1,22 @@
+use crate::text::Style;
+
+/// The styles used for the decorations the library draws itself, such as window borders.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Theme {
+    /// Style of the border lines and corners.
+    pub border: Style,
+    /// Style of the title in the top border.
+    pub title: Style,
+}
+
+impl Theme {
+    pub fn border(mut self, style: Style) -> Self {
+        self.border = style;
+        self
+    }
+
+    pub fn title(mut self, style: Style) -> Self {
+        self.title = style;
+        self
+    }
+}
@@ -0,0 +1,17 @@
+use std::fmt;
+
+use crate::buffer::Buffer;
+use crate::window::{Event, Rect};
+
+/// Content of a window drawn and driven by the application, see `Window::set_widget`.
+pub trait Widget: fmt::Debug {
+    /// Draws the widget into `area` of `buf`, which is cleared beforehand.
+    fn render(&self, buf: &mut Buffer, area: Rect);
+
+    /// Handles an event sent to the window, returning `true` if it was consumed. Consumed
+    /// events are not queued for `Window::take_events`.
+    fn on_event(&mut self, event: Event) -> bool;
+
+    /// Called whenever the window is updated, see `Window::update`.
+    fn update(&mut self) {}
+}
@@ -1,8 +1,66 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
 
-use crate::buffer::{Buffer, BufferDrawIterator};
-use crate::text::Style;
+use crossterm::event::{KeyEvent, MouseEvent};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::buffer::{Buffer, Cell};
+use crate::text::{Alignment, Line, Style};
+use crate::theme::Theme;
+use crate::widget::Widget;
+
+/// An input event forwarded to a window by the `WindowManager`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Event {
+    Key(KeyEvent),
+    /// A mouse event, with `column` and `row` relative to the window.
+    Mouse(MouseEvent),
+    /// Sent by `Window::update`, once per frame or at most every `min_update_interval`.
+    Tick,
+    /// The mouse moved to a new cell of the window, relative to the window.
+    Hover((u16, u16)),
+    /// The window gained (`true`) or lost (`false`) the focus.
+    Focus(bool),
+    /// The window was resized to the given width and height.
+    Resize(u16, u16),
+}
+
+/// An operation on a window that was refused instead of being clamped.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum WindowError {
+    /// The window would have a zero width or height.
+    EmptySize { width: u16, height: u16 },
+    /// The window would be moved entirely outside of the screen.
+    OffScreen { x: u16, y: u16 },
+    /// The window does not fit on the screen.
+    OutOfBounds(Rect),
+}
+
+impl fmt::Display for WindowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowError::EmptySize { width, height } => {
+                write!(f, "window size {width}x{height} is empty")
+            }
+            WindowError::OffScreen { x, y } => {
+                write!(f, "window moved to ({x}, {y}) would be off screen")
+            }
+            WindowError::OutOfBounds(rect) => write!(
+                f,
+                "window at ({}, {}) with size {}x{} does not fit on the screen",
+                rect.x, rect.y, rect.width, rect.height
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WindowError {}
 
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rect {
     pub x: u16,
     pub y: u16,
@@ -10,6 +68,21 @@ pub struct Rect {
     pub height: u16,
 }
 
+/// The direction along which a `Rect` is split: `Horizontal` splits it into columns side by
+/// side, `Vertical` into rows on top of each other.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// Returns the offset and size of each of `n` even parts of `size`.
+fn split(size: u16, n: u16) -> impl Iterator<Item = (u16, u16)> {
+    let part = size.checked_div(n).unwrap_or(0);
+    let remainder = size.checked_rem(n).unwrap_or(0);
+    (0..n).map(move |i| (i * part + i.min(remainder), part + (i < remainder) as u16))
+}
+
 impl Rect {
     pub fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
         Rect {
@@ -24,6 +97,10 @@ impl Rect {
         self.width * self.height
     }
 
+    pub const fn is_empty(self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
     pub const fn left(self) -> u16 {
         self.x
     }
@@ -39,27 +116,397 @@ impl Rect {
     pub const fn bottom(self) -> u16 {
         self.y.saturating_add(self.height)
     }
+
+    /// Shrinks the rect by `margin` on every side.
+    pub fn inner(self, margin: u16) -> Rect {
+        self.inner_xy(margin, margin)
+    }
+
+    /// Shrinks the rect by `horizontal` on the left and right and by `vertical` on the top and
+    /// bottom. A margin larger than half the size collapses the rect to zero size around its
+    /// center.
+    pub fn inner_xy(self, horizontal: u16, vertical: u16) -> Rect {
+        let dx = horizontal.min(self.width / 2);
+        let dy = vertical.min(self.height / 2);
+        Rect {
+            x: self.x.saturating_add(dx),
+            y: self.y.saturating_add(dy),
+            width: self.width.saturating_sub(horizontal.saturating_mul(2)),
+            height: self.height.saturating_sub(vertical.saturating_mul(2)),
+        }
+    }
+
+    /// Returns whether the point lies within the rect, `right` and `bottom` being exclusive.
+    pub const fn contains(self, x: u16, y: u16) -> bool {
+        x >= self.left() && x < self.right() && y >= self.top() && y < self.bottom()
+    }
+
+    /// Returns whether `other` lies entirely within the rect.
+    pub const fn contains_rect(self, other: Rect) -> bool {
+        other.left() >= self.left()
+            && other.right() <= self.right()
+            && other.top() >= self.top()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// Returns the overlapping area of both rects, which has a zero width or height when they
+    /// don't overlap.
+    pub fn intersection(self, other: Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        Rect {
+            x,
+            y,
+            width: right.saturating_sub(x),
+            height: bottom.saturating_sub(y),
+        }
+    }
+
+    /// Splits the rect into `n` columns of equal width, the first ones one column wider when the
+    /// width doesn't divide evenly.
+    pub fn split_horizontal(self, n: u16) -> Vec<Rect> {
+        split(self.width, n)
+            .map(|(x, width)| Rect::new(self.x + x, self.y, width, self.height))
+            .collect()
+    }
+
+    /// Splits the rect into `n` rows of equal height, the first ones one row taller when the
+    /// height doesn't divide evenly.
+    pub fn split_vertical(self, n: u16) -> Vec<Rect> {
+        split(self.height, n)
+            .map(|(y, height)| Rect::new(self.x, self.y + y, self.width, height))
+            .collect()
+    }
+
+    /// Splits the rect in two along `axis`, the first part taking `ratio` of the size, rounded
+    /// and clamped to `0.0..=1.0`.
+    pub fn split_at_ratio(self, ratio: f32, axis: Axis) -> (Rect, Rect) {
+        let size = match axis {
+            Axis::Horizontal => self.width,
+            Axis::Vertical => self.height,
+        };
+        let first = (size as f32 * ratio.clamp(0.0, 1.0)).round() as u16;
+        match axis {
+            Axis::Horizontal => (
+                Rect::new(self.x, self.y, first, self.height),
+                Rect::new(self.x + first, self.y, self.width - first, self.height),
+            ),
+            Axis::Vertical => (
+                Rect::new(self.x, self.y, self.width, first),
+                Rect::new(self.x, self.y + first, self.width, self.height - first),
+            ),
+        }
+    }
+
+    /// Returns the smallest rect containing both rects.
+    pub fn union(self, other: Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Rect {
+            x,
+            y,
+            width: right.saturating_sub(x),
+            height: bottom.saturating_sub(y),
+        }
+    }
 }
 
+/// The characters a window border is drawn with.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum BorderStyle {
+    /// `┌─┐`
+    Plain,
+    /// `╭─╮`
+    #[default]
+    Rounded,
+    /// `╔═╗`
+    Double,
+    /// `┏━┓`
+    Thick,
+    /// `+-+`, for terminals without box drawing characters.
+    Ascii,
+}
+
+struct BorderSymbols {
+    top_left: &'static str,
+    top_right: &'static str,
+    bottom_left: &'static str,
+    bottom_right: &'static str,
+    horizontal: &'static str,
+    vertical: &'static str,
+}
+
+impl BorderStyle {
+    const fn symbols(self) -> BorderSymbols {
+        let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] = match self {
+            BorderStyle::Plain => ["┌", "┐", "└", "┘", "─", "│"],
+            BorderStyle::Rounded => ["╭", "╮", "╰", "╯", "─", "│"],
+            BorderStyle::Double => ["╔", "╗", "╚", "╝", "═", "║"],
+            BorderStyle::Thick => ["┏", "┓", "┗", "┛", "━", "┃"],
+            BorderStyle::Ascii => ["+", "+", "+", "+", "-", "|"],
+        };
+        BorderSymbols {
+            top_left,
+            top_right,
+            bottom_left,
+            bottom_right,
+            horizontal,
+            vertical,
+        }
+    }
+}
+
+/// A rectangular area of the screen with its own content buffer.
+///
+/// A window's widget, see `set_widget`, takes no part in `Clone`, `PartialEq` and `Hash`:
+/// widgets can't be cloned or compared, so a cloned window has no widget, and two windows
+/// differing only in their widgets are equal and hash the same.
 #[derive(Debug, Clone, PartialEq, Hash)]
 pub struct Window {
     area: Rect,
     buffer: Buffer,
+    events: Vec<Event>,
+    /// Offset of the visible area into the content.
+    scroll: (u16, u16),
+    content_size: Option<(u16, u16)>,
+    min_update_interval: Option<Duration>,
+    /// Whether the window wants key repeat events while a key is held down.
+    key_repeat: bool,
+    last_update: Option<Instant>,
+    /// `None` until a theme is set, so `WindowManager::add_window` knows to apply its own.
+    theme: Option<Theme>,
+    /// Applied over the theme's border style, e.g. to highlight the focused window.
+    border_style: Style,
+    /// Window-local position of the mouse while it is over the window.
+    hovered: Option<(u16, u16)>,
+    title: Option<String>,
+    title_alignment: Alignment,
+    /// Appended to a title that had to be cut off to fit, empty to just cut it off.
+    title_ellipsis: String,
+    /// How the last border was drawn, so it can be redrawn.
+    border: Option<BorderStyle>,
+    /// Space kept free inside the border: top, right, bottom, left.
+    padding: (u16, u16, u16, u16),
+    /// Whether the window changed since it was last composited.
+    dirty: bool,
+    /// Hidden windows are kept, but neither drawn nor hit by the mouse.
+    minimized: bool,
+    /// Bounds for `resize` and `resize_by`, as (width, height).
+    min_size: (u16, u16),
+    max_size: (u16, u16),
+    widget: WidgetSlot,
+    /// Where the terminal cursor is shown while the window has focus, relative to the window.
+    cursor: Option<(u16, u16)>,
+}
+
+/// Holds the widget of a window. Widgets can't be cloned or compared, so a cloned window is
+/// left without one, and widgets are ignored when comparing windows.
+#[derive(Debug, Default)]
+struct WidgetSlot(Option<Box<dyn Widget>>);
+
+impl Clone for WidgetSlot {
+    fn clone(&self) -> Self {
+        WidgetSlot(None)
+    }
 }
 
+impl PartialEq for WidgetSlot {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Hash for WidgetSlot {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
 
 impl Window {
     pub fn new(area: Rect) -> Self {
         Window {
             area,
             buffer: Buffer::empty(area.width, area.height),
+            events: Vec::new(),
+            scroll: (0, 0),
+            content_size: None,
+            min_update_interval: None,
+            key_repeat: false,
+            last_update: None,
+            theme: None,
+            border_style: Style::default(),
+            hovered: None,
+            title: None,
+            title_alignment: Alignment::Left,
+            title_ellipsis: String::new(),
+            border: None,
+            padding: (0, 0, 0, 0),
+            dirty: true,
+            minimized: false,
+            min_size: (3, 3),
+            max_size: (u16::MAX, u16::MAX),
+            widget: WidgetSlot::default(),
+            cursor: None,
         }
     }
 
+    /// Creates a window with a border and title, and `content` wrapped into the area inside the
+    /// border.
+    pub fn bordered(area: Rect, title: &str, content: &str) -> Self {
+        let mut win = Window::new(area);
+        win.draw_border(title);
+
+        let inner = Rect::new(
+            1,
+            1,
+            area.width.saturating_sub(2),
+            area.height.saturating_sub(2),
+        );
+        win.buffer.set_string_wrapped(inner, content, Style::default());
+        win
+    }
+
+    /// Returns an empty window with the same area and settings, such as the theme, border,
+    /// title and padding, e.g. to spawn many similar windows. The border is drawn again, but
+    /// nothing else of the content is kept.
+    pub fn template(&self) -> Window {
+        let mut win = Window {
+            content_size: self.content_size,
+            min_update_interval: self.min_update_interval,
+            key_repeat: self.key_repeat,
+            theme: self.theme,
+            border_style: self.border_style,
+            title: self.title.clone(),
+            title_alignment: self.title_alignment,
+            title_ellipsis: self.title_ellipsis.clone(),
+            border: self.border,
+            padding: self.padding,
+            min_size: self.min_size,
+            max_size: self.max_size,
+            ..Window::new(self.area)
+        };
+        win.resize_content();
+        win.redraw_border();
+        win
+    }
+
+    /// Resizes the window. The content is resized along with it, but never below the size set
+    /// with `set_content_size`.
+    ///
+    /// The size is kept between `min_size` and `max_size`, the minimum taking precedence. If
+    /// the size changes, an `Event::Resize` is queued.
     pub fn resize(&mut self, width: u16, height: u16) {
-        self.area.width = width;
-        self.area.height = height;
-        self.buffer.resize(width, height);
+        let (old_width, old_height) = (self.area.width, self.area.height);
+        self.area.width = width.min(self.max_size.0).max(self.min_size.0);
+        self.area.height = height.min(self.max_size.1).max(self.min_size.1);
+        self.resize_content();
+        if (self.area.width, self.area.height) != (old_width, old_height) {
+            self.event(Event::Resize(self.area.width, self.area.height));
+        }
+    }
+
+    /// Sets the smallest size `resize` and `resize_by` shrink the window to, 3x3 by default so
+    /// a border always fits. The current size is left as it is.
+    pub fn set_min_size(&mut self, width: u16, height: u16) {
+        self.min_size = (width, height);
+    }
+
+    pub fn min_size(&self) -> (u16, u16) {
+        self.min_size
+    }
+
+    /// Sets the largest size `resize` and `resize_by` grow the window to, unlimited by default.
+    /// The current size is left as it is.
+    pub fn set_max_size(&mut self, width: u16, height: u16) {
+        self.max_size = (width, height);
+    }
+
+    pub fn max_size(&self) -> (u16, u16) {
+        self.max_size
+    }
+
+    /// Like `resize`, but refuses to shrink the window to nothing.
+    pub fn try_resize(&mut self, width: u16, height: u16) -> Result<(), WindowError> {
+        if width == 0 || height == 0 {
+            return Err(WindowError::EmptySize { width, height });
+        }
+        self.resize(width, height);
+        Ok(())
+    }
+
+    /// Sets the size of the content independently of the window, so it can hold more than is
+    /// visible at once. The window then shows the part of the content at the scroll offset.
+    pub fn set_content_size(&mut self, width: u16, height: u16) {
+        self.content_size = Some((width, height));
+        self.resize_content();
+    }
+
+    /// Returns the size of the content, which is at least the size of the window.
+    pub fn content_size(&self) -> (u16, u16) {
+        (self.buffer.width, self.buffer.height)
+    }
+
+    /// Resizes the content to the larger of the window and content sizes, keeping every cell
+    /// at its position. `Buffer::resize` keeps the cells in order instead, which would shift
+    /// the rows whenever the width changes.
+    fn resize_content(&mut self) {
+        let (width, height) = self.content_size.unwrap_or_default();
+        let (width, height) = (width.max(self.area.width), height.max(self.area.height));
+        if (width, height) != (self.buffer.width, self.buffer.height) {
+            let old = self.buffer.clone();
+            self.buffer.resize(width, height);
+            self.buffer.reset();
+            self.buffer
+                .blit((0, 0), &old, Rect::new(0, 0, old.width, old.height));
+        }
+        let max = self.max_scroll();
+        self.scroll = (self.scroll.0.min(max.0), self.scroll.1.min(max.1));
+        self.dirty = true;
+    }
+
+    pub fn scroll(&self) -> (u16, u16) {
+        self.scroll
+    }
+
+    /// Scrolls so the content at (x, y) shows in the top-left corner, as far as the content
+    /// reaches. Content is always written at unscrolled coordinates.
+    pub fn scroll_to(&mut self, x: u16, y: u16) {
+        let max = self.max_scroll();
+        self.scroll = (x.min(max.0), y.min(max.1));
+        self.dirty = true;
+    }
+
+    /// Scrolls by `dx` columns and `dy` rows, negative values scrolling left and up.
+    pub fn scroll_by(&mut self, dx: i16, dy: i16) {
+        self.scroll_to(
+            self.scroll.0.saturating_add_signed(dx),
+            self.scroll.1.saturating_add_signed(dy),
+        );
+    }
+
+    /// Scrolls up by the window's height, keeping one line of the previous page in view.
+    pub fn scroll_page_up(&mut self) {
+        self.scroll.1 = self.scroll.1.saturating_sub(self.page_height());
+        self.dirty = true;
+    }
+
+    /// Scrolls down by the window's height, keeping one line of the previous page in view.
+    pub fn scroll_page_down(&mut self) {
+        self.scroll.1 = (self.scroll.1 + self.page_height()).min(self.max_scroll().1);
+        self.dirty = true;
+    }
+
+    fn page_height(&self) -> u16 {
+        self.area.height.saturating_sub(1).max(1)
+    }
+
+    fn max_scroll(&self) -> (u16, u16) {
+        (
+            self.buffer.width.saturating_sub(self.area.width),
+            self.buffer.height.saturating_sub(self.area.height),
+        )
     }
 
     pub fn resize_by(&mut self, width: i16, height: i16) {
@@ -71,16 +518,38 @@ impl Window {
 
     pub fn reset(&mut self) {
         self.buffer.reset();
+        self.dirty = true;
     }
 
     pub fn move_to(&mut self, x: u16, y: u16) {
         self.area.x = x;
         self.area.y = y;
+        self.dirty = true;
+    }
+
+    /// Like `move_to`, but refuses to move the window entirely outside of `screen`.
+    pub fn try_move_to(&mut self, x: u16, y: u16, screen: Rect) -> Result<(), WindowError> {
+        let area = Rect { x, y, ..self.area };
+        if area.intersection(screen).is_empty() {
+            return Err(WindowError::OffScreen { x, y });
+        }
+        self.move_to(x, y);
+        Ok(())
     }
 
     pub fn move_by(&mut self, x: i16, y: i16) {
         self.area.x = self.area.x.saturating_add_signed(x);
         self.area.y = self.area.y.saturating_add_signed(y);
+        self.dirty = true;
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.area
+    }
+
+    /// Whether the windows cover any common cell. Windows that only touch edges don't overlap.
+    pub fn overlaps(&self, other: &Window) -> bool {
+        !self.area.intersection(other.area).is_empty()
     }
 
     pub fn pos(&self) -> (u16, u16) {
@@ -95,18 +564,187 @@ impl Window {
         self.area.height
     }
 
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Copies the visible part of the window's content into `buffer` at the window's position,
+    /// clipping whatever falls outside of it. Cells with `skip` set leave `buffer` untouched.
     pub fn draw(&self, buffer: &mut Buffer) {
-        buffer.insert(self.area.x, self.area.y, &self.buffer);
+        let visible = self
+            .area
+            .intersection(Rect::new(0, 0, buffer.width, buffer.height));
+        let (scroll_x, scroll_y) = self.scroll;
+        buffer.invalidate_rows(visible.top()..visible.bottom());
+        for y in visible.top()..visible.bottom() {
+            for x in visible.left()..visible.right() {
+                let src = self
+                    .buffer
+                    .index_of(x - self.area.x + scroll_x, y - self.area.y + scroll_y);
+                let cell = &self.buffer.content[src];
+                if cell.skip {
+                    continue;
+                }
+                let dst = buffer.index_of(x, y);
+                buffer.content[dst] = cell.composite(&buffer.content[dst]);
+            }
+        }
+    }
+
+    /// Queues an event for the window, to be picked up with `take_events`.
+    ///
+    /// An `Event::Hover`, `Event::Focus` or `Event::Resize` replaces one of the same kind still
+    /// waiting to be picked up, so moving the mouse or resizing quickly doesn't flood the window
+    /// with events. Only the latest state matters for these.
+    pub fn event(&mut self, event: Event) {
+        if let Some(widget) = &mut self.widget.0 {
+            if widget.on_event(event) {
+                self.dirty = true;
+                return;
+            }
+        }
+        match (event, self.events.last_mut()) {
+            (Event::Hover(_), Some(last @ Event::Hover(_)))
+            | (Event::Focus(_), Some(last @ Event::Focus(_)))
+            | (Event::Resize(..), Some(last @ Event::Resize(..))) => *last = event,
+            _ => self.events.push(event),
+        }
+    }
+
+    /// Whether the window receives an `Event::Key` with `KeyEventKind::Repeat` while a key is
+    /// held down, e.g. to keep scrolling. Otherwise only presses are forwarded.
+    pub fn set_key_repeat(&mut self, enabled: bool) {
+        self.key_repeat = enabled;
+    }
+
+    pub fn key_repeat(&self) -> bool {
+        self.key_repeat
+    }
+
+    /// Hides the window without losing its content, until `restore` is called.
+    pub fn minimize(&mut self) {
+        self.minimized = true;
+        self.dirty = true;
     }
 
-    pub fn content_iter(&self) -> BufferDrawIterator {
-        self.buffer.draw()
+    pub fn restore(&mut self) {
+        self.minimized = false;
+        self.dirty = true;
     }
 
-    pub fn set_lines<S>(&mut self, x: u16, y: u16, string: S, style: Style)
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    /// Returns the window-local position of the mouse, if it is over the window.
+    pub fn hovered(&self) -> Option<(u16, u16)> {
+        self.hovered
+    }
+
+    pub(crate) fn set_hovered(&mut self, hovered: Option<(u16, u16)>) {
+        self.hovered = hovered;
+    }
+
+    /// Returns all events received since the last call.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Queues an `Event::Tick`, unless one is already waiting to be picked up.
+    pub fn update(&mut self) {
+        if let Some(widget) = &mut self.widget.0 {
+            widget.update();
+            self.dirty = true;
+        }
+        if self.events.last() != Some(&Event::Tick) {
+            self.events.push(Event::Tick);
+        }
+    }
+
+    /// Limits how often the `WindowManager` updates the window, `None` updating it every frame.
+    pub fn set_min_update_interval(&mut self, interval: Option<Duration>) {
+        self.min_update_interval = interval;
+    }
+
+    /// Updates the window if `min_update_interval` has passed since the last update.
+    pub(crate) fn update_at(&mut self, now: Instant) {
+        let due = match (self.min_update_interval, self.last_update) {
+            (Some(interval), Some(last)) => now.saturating_duration_since(last) >= interval,
+            _ => true,
+        };
+        if due {
+            self.update();
+            self.last_update = Some(now);
+        }
+    }
+
+    /// Shows the terminal cursor at (x, y) relative to the window while it has focus, e.g. in a
+    /// text input.
+    pub fn set_cursor(&mut self, x: u16, y: u16) {
+        self.cursor = Some((x, y));
+        self.dirty = true;
+    }
+
+    pub fn hide_cursor(&mut self) {
+        self.cursor = None;
+        self.dirty = true;
+    }
+
+    pub fn cursor(&self) -> Option<(u16, u16)> {
+        self.cursor
+    }
+
+    /// Makes `widget` draw the content of the window, inside the border and padding. It is
+    /// rendered by `render_widget`, which the `WindowManager` calls before compositing.
+    ///
+    /// Cloning the window leaves the clone without the widget, and comparing windows ignores
+    /// it.
+    pub fn set_widget(&mut self, widget: impl Widget + 'static) {
+        self.widget = WidgetSlot(Some(Box::new(widget)));
+        self.dirty = true;
+    }
+
+    pub fn widget(&self) -> Option<&dyn Widget> {
+        self.widget.0.as_deref()
+    }
+
+    /// Removes the widget and returns it, leaving the content it drew in place.
+    pub fn take_widget(&mut self) -> Option<Box<dyn Widget>> {
+        self.widget.0.take()
+    }
+
+    /// Clears the visible content area and draws the widget into it, if there is one.
+    pub fn render_widget(&mut self) {
+        let Some(widget) = &self.widget.0 else {
+            return;
+        };
+        let mut area = self.content_area();
+        area.x += self.scroll.0;
+        area.y += self.scroll.1;
+        self.buffer.fill(area, &Cell::default());
+        widget.render(&mut self.buffer, area);
+        self.dirty = true;
+    }
+
+    /// Iterates over the visible cells, with coordinates relative to the window.
+    pub fn content_iter(&self) -> impl Iterator<Item = (u16, u16, &Cell)> {
+        let viewport = Rect::new(
+            self.scroll.0,
+            self.scroll.1,
+            self.area.width,
+            self.area.height,
+        );
+        self.buffer
+            .draw()
+            .filter(move |&(x, y, _)| viewport.contains(x, y))
+            .map(move |(x, y, cell)| (x - viewport.x, y - viewport.y, cell))
+    }
+
+    pub fn set_lines<S>(&mut self, x: u16, y: u16, string: S, style: Style) -> u16
     where
         S: AsRef<str>,
     {
+        self.dirty = true;
         self.buffer.set_lines(x, y, string, style)
     }
 
@@ -114,6 +752,7 @@ impl Window {
     where
         S: AsRef<str>,
     {
+        self.dirty = true;
         self.buffer.set_string(x, y, string, style)
     }
 
@@ -128,49 +767,744 @@ impl Window {
     where
         S: AsRef<str>,
     {
+        self.dirty = true;
         self.buffer.set_stringn(x, y, string, width, style)
     }
 
+    pub fn set_line(&mut self, x: u16, y: u16, line: &Line) -> (u16, u16) {
+        self.dirty = true;
+        self.buffer.set_line(x, y, line)
+    }
+
+    /// Returns the theme set with `set_theme`, or the default theme if none was set.
+    pub fn theme(&self) -> &Theme {
+        const DEFAULT: &Theme = &Theme {
+            border: Style::new(),
+            title: Style::new(),
+        };
+        self.theme.as_ref().unwrap_or(DEFAULT)
+    }
+
+    /// Whether a theme was set with `set_theme`.
+    pub(crate) fn has_theme(&self) -> bool {
+        self.theme.is_some()
+    }
+
+    /// Sets the styles used for decorations, redrawing the border if one was drawn.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = Some(*theme);
+        self.redraw_border();
+    }
+
+    /// Sets a style applied over the theme's border style, redrawing the border if one was
+    /// drawn.
+    pub fn set_border_style(&mut self, style: Style) {
+        self.border_style = style;
+        self.redraw_border();
+    }
+
+    /// Sets the space kept free inside the border, as (top, right, bottom, left).
+    pub fn set_padding(&mut self, padding: (u16, u16, u16, u16)) {
+        self.padding = padding;
+    }
+
+    /// Returns the window-local area left for content inside the border and padding.
+    pub fn content_area(&self) -> Rect {
+        let border = self.border.is_some() as u16;
+        let (top, right, bottom, left) = self.padding;
+        let (left, top) = (left.saturating_add(border), top.saturating_add(border));
+        let (right, bottom) = (right.saturating_add(border), bottom.saturating_add(border));
+        Rect {
+            x: left,
+            y: top,
+            width: self.area.width.saturating_sub(left.saturating_add(right)),
+            height: self.area.height.saturating_sub(top.saturating_add(bottom)),
+        }
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Sets the title shown in the top border, redrawing the border if one was drawn.
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.title = Some(title.into());
+        self.redraw_border();
+    }
+
+    /// Sets where the title is placed along the top border, redrawing the border if one was
+    /// drawn.
+    pub fn set_title_alignment(&mut self, alignment: Alignment) {
+        self.title_alignment = alignment;
+        self.redraw_border();
+    }
+
+    /// Sets what replaces the end of a title too long to fit in the top border, e.g. `"…"`,
+    /// redrawing the border if one was drawn.
+    pub fn set_title_ellipsis(&mut self, ellipsis: impl Into<String>) {
+        self.title_ellipsis = ellipsis.into();
+        self.redraw_border();
+    }
+
+    /// Draws a border around the window with the title set by `set_title`, using the border
+    /// style of the previous border.
+    pub fn draw_decorated(&mut self) {
+        let style = self.border.unwrap_or_default();
+        let title = self.title.take().unwrap_or_default();
+        self.draw_border_full(&title, self.title_alignment, style);
+    }
+
+    pub(crate) fn redraw_border(&mut self) {
+        if let Some(style) = self.border {
+            // Clear the old border first, so colors the new style leaves unset don't linger.
+            // Only the symbol and style, tags and links on the border stay.
+            let (width, height) = (self.area.width, self.area.height);
+            for y in 0..height {
+                for x in 0..width {
+                    if y == 0 || y == height - 1 || x == 0 || x == width - 1 {
+                        let i = self.buffer.index_of(x, y);
+                        self.buffer.content[i]
+                            .set_symbol(" ")
+                            .set_style(Style::reset());
+                    }
+                }
+            }
+            let title = self.title.take().unwrap_or_default();
+            self.draw_border_full(&title, self.title_alignment, style);
+        }
+    }
+
     pub fn draw_border(&mut self, title: &str) {
+        self.draw_border_full(title, self.title_alignment, BorderStyle::Rounded)
+    }
+
+    /// Draws a border with the title placed along the top edge according to `alignment`. The
+    /// title is truncated so it never covers the corners.
+    pub fn draw_border_with(&mut self, title: &str, alignment: Alignment) {
+        self.draw_border_full(title, alignment, BorderStyle::Rounded)
+    }
+
+    /// Draws a border using the line characters of `style`.
+    pub fn draw_border_styled(&mut self, title: &str, style: BorderStyle) {
+        self.draw_border_full(title, self.title_alignment, style)
+    }
+
+    fn draw_border_full(&mut self, title: &str, alignment: Alignment, style: BorderStyle) {
+        let theme = *self.theme();
         let buf = &mut self.buffer;
-        let area = &self.area;
+        let width = self.area.width;
+        let height = self.area.height;
+        let border = theme.border.patch(self.border_style);
+        let set = style.symbols();
+
+        self.title = (!title.is_empty()).then(|| title.to_string());
+        self.title_alignment = alignment;
+        self.border = Some(style);
+        self.dirty = true;
+        // A border needs at least two columns and rows, smaller windows are left without one.
+        if width < 2 || height < 2 {
+            return;
+        }
 
         // Top
-        buf.set_stringn(area.x, area.y, "╭", 1, Style::default());
-        let len = title.len().min(area.width as usize - 2);
-        buf.set_stringn(area.x + 1, area.y, title, len, Style::default());
-        for i in (len as u16 + 1)..(area.width - 1) {
-            buf.set_stringn(area.x + i, area.y, "─", 1, Style::default());
+        let inner = width as usize - 2;
+        let shown = truncate(title, inner, &self.title_ellipsis);
+        let len = shown.width().min(inner);
+        let x = match alignment {
+            Alignment::Left => 1,
+            Alignment::Center => 1 + (inner - len) / 2,
+            Alignment::Right => 1 + inner - len,
+        } as u16;
+        buf.set_stringn(0, 0, set.top_left, 1, border);
+        for i in (1..x).chain(x + len as u16..width - 1) {
+            buf.set_stringn(i, 0, set.horizontal, 1, border);
         }
-        buf.set_stringn(area.x + area.width - 1, area.y, "╮", 1, Style::default());
+        buf.set_stringn(x, 0, &shown, len, theme.title);
+        buf.set_stringn(width - 1, 0, set.top_right, 1, border);
 
         // Middle
-        for i in 1..area.height {
-            buf.set_stringn(area.x, area.y + i, "│", 1, Style::default());
-            buf.set_stringn(
-                area.x + area.width - 1,
-                area.y + i,
-                "│",
-                1,
-                Style::default(),
-                );
+        for i in 1..height - 1 {
+            buf.set_stringn(0, i, set.vertical, 1, border);
+            buf.set_stringn(width - 1, i, set.vertical, 1, border);
         }
 
         // Bottom
-        buf.set_stringn(area.x, area.y + area.height, "╰", 1, Style::default());
-        for i in 1..area.width - 1 {
-            buf.set_stringn(area.x + i, area.y + area.height, "─", 1, Style::default());
-        }
-        buf.set_stringn(
-            area.x + area.width - 1,
-            area.y + area.height,
-            "╯",
-            1,
-            Style::default(),
-            );
+        buf.set_stringn(0, height - 1, set.bottom_left, 1, border);
+        for i in 1..width - 1 {
+            buf.set_stringn(i, height - 1, set.horizontal, 1, border);
+        }
+        buf.set_stringn(width - 1, height - 1, set.bottom_right, 1, border);
     }
 
     pub fn set_style(&mut self, area: Rect, style: Style) {
+        self.dirty = true;
         self.buffer.set_style(area, style)
     }
+
+    /// Whether the window changed since it was last composited by the `WindowManager`.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub(crate) fn clear_dirty(&mut self) {
+        self.dirty = false;
+    }
+}
+
+/// Cuts `text` down to `width` columns, ending it with `ellipsis` if it had to be cut and the
+/// ellipsis fits.
+fn truncate<'a>(text: &'a str, width: usize, ellipsis: &str) -> Cow<'a, str> {
+    if text.width() <= width || ellipsis.width() > width {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::new();
+    let mut out_width = 0;
+    for g in text.graphemes(true) {
+        if out_width + g.width() + ellipsis.width() > width {
+            break;
+        }
+        out.push_str(g);
+        out_width += g.width();
+    }
+    out.push_str(ellipsis);
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::style::Color;
+
+    fn symbol(win: &Window, x: u16, y: u16) -> &str {
+        &win.buffer.content[win.buffer.index_of(x, y)].symbol
+    }
+
+    #[test]
+    fn rect_contains() {
+        let rect = Rect::new(2, 3, 4, 5);
+        assert!(rect.contains(2, 3));
+        assert!(rect.contains(5, 3));
+        assert!(rect.contains(2, 7));
+        assert!(rect.contains(5, 7));
+        assert!(rect.contains(3, 5));
+
+        assert!(!rect.contains(1, 3));
+        assert!(!rect.contains(6, 3));
+        assert!(!rect.contains(2, 2));
+        assert!(!rect.contains(2, 8));
+    }
+
+    #[test]
+    fn rect_contains_rect() {
+        let rect = Rect::new(2, 3, 4, 5);
+        assert!(rect.contains_rect(rect));
+        assert!(rect.contains_rect(Rect::new(3, 4, 2, 2)));
+        assert!(!rect.contains_rect(Rect::new(3, 4, 4, 2)));
+        assert!(!rect.contains_rect(Rect::new(1, 4, 2, 2)));
+    }
+
+    #[test]
+    fn rect_inner() {
+        let rect = Rect::new(3, 4, 10, 10);
+        assert_eq!(rect.inner(2), Rect::new(5, 6, 6, 6));
+        assert_eq!(rect.inner(6), Rect::new(8, 9, 0, 0));
+        assert_eq!(rect.inner_xy(1, 3), Rect::new(4, 7, 8, 4));
+        assert_eq!(rect.inner(u16::MAX), Rect::new(8, 9, 0, 0));
+    }
+
+    #[test]
+    fn rect_disjoint() {
+        let a = Rect::new(0, 0, 4, 4);
+        let b = Rect::new(10, 6, 2, 2);
+        assert!(a.intersection(b).is_empty());
+        assert_eq!(a.union(b), Rect::new(0, 0, 12, 8));
+    }
+
+    #[test]
+    fn rect_contained() {
+        let outer = Rect::new(2, 2, 10, 10);
+        let inner = Rect::new(4, 5, 3, 2);
+        assert_eq!(outer.intersection(inner), inner);
+        assert_eq!(inner.intersection(outer), inner);
+        assert_eq!(outer.union(inner), outer);
+    }
+
+    #[test]
+    fn rect_partial_overlap() {
+        let a = Rect::new(0, 0, 6, 4);
+        let b = Rect::new(4, 2, 6, 6);
+        assert_eq!(a.intersection(b), Rect::new(4, 2, 2, 2));
+        assert_eq!(a.union(b), Rect::new(0, 0, 10, 8));
+    }
+
+    #[test]
+    fn rect_saturates() {
+        let a = Rect::new(u16::MAX - 2, 0, 10, 1);
+        let b = Rect::new(0, 0, 4, 1);
+        assert_eq!(a.union(b), Rect::new(0, 0, u16::MAX, 1));
+        assert!(a.intersection(b).is_empty());
+    }
+
+    #[test]
+    fn draw_clips_to_target() {
+        let mut target = Buffer::empty(8, 6);
+        let mut win = Window::new(Rect::new(5, 3, 10, 10));
+        for y in 0..10 {
+            win.set_string(0, y, "xxxxxxxxxx", Style::default());
+        }
+        win.draw(&mut target);
+
+        for (i, cell) in target.content.iter().enumerate() {
+            let (x, y) = target.pos_of(i);
+            let inside = x >= 5 && y >= 3;
+            assert_eq!(cell.symbol == "x", inside, "cell at ({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn scroll_page() {
+        let mut win = Window::new(Rect::new(0, 0, 10, 5));
+        win.set_content_size(10, 20);
+        for y in 0..20 {
+            win.set_string(0, y, format!("line {y}"), Style::default());
+        }
+
+        win.scroll_page_down();
+        assert_eq!(win.scroll(), (0, 4));
+        let mut target = Buffer::empty(10, 5);
+        win.draw(&mut target);
+        assert_eq!(target.content[0].symbol, "l");
+        assert_eq!(target.content[5].symbol, "4");
+        let first = win
+            .content_iter()
+            .next()
+            .map(|(x, y, c)| (x, y, c.symbol.clone()));
+        assert_eq!(first, Some((0, 0, "l".to_string())));
+
+        for _ in 0..4 {
+            win.scroll_page_down();
+        }
+        assert_eq!(win.scroll(), (0, 15));
+
+        win.scroll_page_up();
+        assert_eq!(win.scroll(), (0, 11));
+        for _ in 0..4 {
+            win.scroll_page_up();
+        }
+        assert_eq!(win.scroll(), (0, 0));
+    }
+
+    #[test]
+    fn content_larger_than_window() {
+        let mut win = Window::new(Rect::new(0, 0, 40, 20));
+        win.set_content_size(80, 200);
+        assert_eq!(win.content_size(), (80, 200));
+        win.set_string(70, 150, "far", Style::default());
+
+        for _ in 0..7 {
+            win.scroll_page_down();
+        }
+        assert_eq!(win.scroll(), (0, 133));
+        let mut target = Buffer::empty(40, 20);
+        win.draw(&mut target);
+        // Out of view horizontally
+        assert!(target.content.iter().all(|c| c.symbol == " "));
+
+        win.set_string(5, 150, "near", Style::default());
+        win.draw(&mut target);
+        assert_eq!(target.content[target.index_of(5, 17)].symbol, "n");
+
+        win.resize(50, 30);
+        assert_eq!(win.content_size(), (80, 200));
+        assert_eq!(win.scroll(), (0, 133));
+        win.resize(100, 10);
+        assert_eq!(win.content_size(), (100, 200));
+
+        // Both width changes keep the content in place.
+        win.resize(40, 20);
+        assert_eq!(win.content_size(), (80, 200));
+        win.scroll_to(40, 145);
+        assert_eq!(win.scroll(), (40, 145));
+        let mut target = Buffer::empty(40, 20);
+        win.draw(&mut target);
+        assert_eq!(target.to_lines()[5], format!("{:>33}{:7}", "far", ""));
+        win.scroll_to(0, 145);
+        win.draw(&mut target);
+        assert_eq!(target.to_lines()[5], format!("{:<40}", "     near"));
+    }
+
+    fn row(win: &Window, y: u16) -> String {
+        (0..win.width()).map(|x| symbol(win, x, y)).collect()
+    }
+
+    #[test]
+    fn bordered() {
+        let win = Window::bordered(
+            Rect::new(3, 3, 12, 5),
+            "Note",
+            "The quick brown fox jumps over",
+        );
+        assert_eq!(row(&win, 0), "╭Note──────╮");
+        assert_eq!(row(&win, 1), "│The quick │");
+        assert_eq!(row(&win, 2), "│brown fox │");
+        assert_eq!(row(&win, 3), "│jumps over│");
+        assert_eq!(row(&win, 4), "╰──────────╯");
+    }
+
+    #[test]
+    fn draw_border_uses_local_coordinates() {
+        let mut win = Window::new(Rect::new(4, 4, 10, 6));
+        win.draw_border("Title");
+
+        assert_eq!(symbol(&win, 0, 0), "╭");
+        assert_eq!(symbol(&win, 9, 0), "╮");
+        assert_eq!(symbol(&win, 0, 5), "╰");
+        assert_eq!(symbol(&win, 9, 5), "╯");
+    }
+
+    #[test]
+    fn draw_border_bottom_row() {
+        let mut win = Window::new(Rect::new(0, 0, 5, 3));
+        win.draw_border("");
+
+        let row = |y| (0..5).map(|x| symbol(&win, x, y)).collect::<String>();
+        assert_eq!(row(0), "╭───╮");
+        assert_eq!(row(1), "│   │");
+        assert_eq!(row(2), "╰───╯");
+    }
+
+    #[test]
+    fn try_resize_empty() {
+        let mut win = Window::new(Rect::new(0, 0, 4, 4));
+        assert_eq!(
+            win.try_resize(0, 3),
+            Err(WindowError::EmptySize {
+                width: 0,
+                height: 3
+            })
+        );
+        assert_eq!(win.rect(), Rect::new(0, 0, 4, 4));
+        assert_eq!(win.try_resize(3, 5), Ok(()));
+        assert_eq!(win.rect(), Rect::new(0, 0, 3, 5));
+    }
+
+    #[test]
+    fn try_move_off_screen() {
+        let screen = Rect::new(0, 0, 20, 10);
+        let mut win = Window::new(Rect::new(0, 0, 4, 4));
+        assert_eq!(
+            win.try_move_to(20, 2, screen),
+            Err(WindowError::OffScreen { x: 20, y: 2 })
+        );
+        assert_eq!(win.pos(), (0, 0));
+        assert_eq!(win.try_move_to(19, 9, screen), Ok(()));
+        assert_eq!(win.pos(), (19, 9));
+    }
+
+    #[test]
+    fn draw_blends_translucent_cells() {
+        const BLUE: Color = Color::Rgb { r: 0, g: 0, b: 200 };
+        const RED: Color = Color::Rgb { r: 200, g: 0, b: 0 };
+        const PURPLE: Color = Color::Rgb {
+            r: 100,
+            g: 0,
+            b: 100,
+        };
+
+        let mut target = Buffer::empty(3, 1);
+        for cell in &mut target.content {
+            cell.set_char('x').set_bg(BLUE);
+        }
+
+        let mut win = Window::new(Rect::new(1, 0, 2, 1));
+        win.set_string(0, 0, "ab", Style::default().bg(RED));
+        let i = win.buffer.index_of(0, 0);
+        win.buffer.content[i].set_alpha(128);
+        win.draw(&mut target);
+
+        let blended = &target.content[1];
+        assert_eq!(blended.symbol, "a");
+        assert_eq!(blended.bg, PURPLE);
+        assert_eq!(blended.alpha, u8::MAX);
+        assert_eq!(target.content[2].bg, RED);
+    }
+
+    #[test]
+    fn draw_border_title_alignment() {
+        let mut win = Window::new(Rect::new(0, 0, 20, 3));
+        let title_column = |win: &Window| (0..20).find(|&x| symbol(win, x, 0) == "T");
+
+        win.draw_border_with("Title", Alignment::Left);
+        assert_eq!(title_column(&win), Some(1));
+        assert_eq!(row(&win, 0), "╭Title─────────────╮");
+
+        win.draw_border_with("Title", Alignment::Center);
+        assert_eq!(title_column(&win), Some(7));
+        assert_eq!(row(&win, 0), "╭──────Title───────╮");
+
+        win.draw_border_with("Title", Alignment::Right);
+        assert_eq!(title_column(&win), Some(14));
+        assert_eq!(row(&win, 0), "╭─────────────Title╮");
+
+        win.draw_border_with("A very long window title", Alignment::Center);
+        assert_eq!(row(&win, 0), "╭A very long window╮");
+    }
+
+    #[test]
+    fn draw_border_styles() {
+        let cases = [
+            (BorderStyle::Plain, ["┌", "┐", "└", "┘"]),
+            (BorderStyle::Rounded, ["╭", "╮", "╰", "╯"]),
+            (BorderStyle::Double, ["╔", "╗", "╚", "╝"]),
+            (BorderStyle::Thick, ["┏", "┓", "┗", "┛"]),
+            (BorderStyle::Ascii, ["+", "+", "+", "+"]),
+        ];
+        for (style, corners) in cases {
+            let mut win = Window::new(Rect::new(2, 2, 6, 4));
+            win.draw_border_styled("", style);
+            let drawn = [
+                symbol(&win, 0, 0),
+                symbol(&win, 5, 0),
+                symbol(&win, 0, 3),
+                symbol(&win, 5, 3),
+            ];
+            assert_eq!(drawn, corners, "{style:?}");
+        }
+
+        let mut win = Window::new(Rect::new(0, 0, 5, 3));
+        win.draw_border_styled("", BorderStyle::Ascii);
+        assert_eq!(row(&win, 0), "+---+");
+        assert_eq!(row(&win, 1), "|   |");
+    }
+
+    #[test]
+    fn border_style() {
+        let mut win = Window::new(Rect::new(0, 0, 6, 4));
+        win.set_border_style(Style::default().fg(Color::Red));
+        win.draw_border("Hi");
+
+        let fg = |win: &Window, x, y| win.buffer.content[win.buffer.index_of(x, y)].fg;
+        for (x, y) in [(0, 0), (5, 0), (0, 3), (5, 3)] {
+            assert_eq!(fg(&win, x, y), Color::Red);
+        }
+        assert_eq!(fg(&win, 1, 0), Color::Reset);
+
+        win.buffer[(0, 0)].set_tag(Some(7));
+        win.set_border_style(Style::default());
+        assert_eq!(fg(&win, 0, 0), Color::Reset);
+        assert_eq!(win.buffer[(0, 0)].tag, Some(7));
+    }
+
+    #[test]
+    fn set_title() {
+        let mut win = Window::new(Rect::new(3, 3, 10, 3));
+        win.set_title("Files");
+        assert_eq!(row(&win, 0), " ".repeat(10));
+
+        win.draw_decorated();
+        assert_eq!(row(&win, 0), "╭Files───╮");
+
+        win.set_title("Edit");
+        assert_eq!(win.title(), Some("Edit"));
+        assert_eq!(row(&win, 0), "╭Edit────╮");
+    }
+
+    #[test]
+    fn content_area() {
+        let mut win = Window::new(Rect::new(5, 5, 10, 10));
+        assert_eq!(win.content_area(), Rect::new(0, 0, 10, 10));
+
+        win.set_padding((1, 1, 1, 1));
+        assert_eq!(win.content_area(), Rect::new(1, 1, 8, 8));
+
+        win.draw_border("");
+        assert_eq!(win.content_area(), Rect::new(2, 2, 6, 6));
+
+        win.set_padding((0, 3, 2, 1));
+        assert_eq!(win.content_area(), Rect::new(2, 1, 4, 6));
+    }
+
+    #[test]
+    fn title_alignment_and_ellipsis() {
+        let mut win = Window::new(Rect::new(0, 0, 12, 3));
+        win.set_title_alignment(Alignment::Center);
+        win.draw_border("Log");
+        assert_eq!(row(&win, 0), "╭───Log────╮");
+
+        win.set_title_ellipsis("…");
+        win.set_title("Downloads folder");
+        assert_eq!(row(&win, 0), "╭Downloads…╮");
+
+        win.set_title_alignment(Alignment::Left);
+        win.set_title("Notes");
+        assert_eq!(row(&win, 0), "╭Notes─────╮");
+    }
+
+    #[test]
+    fn overlaps() {
+        let a = Window::new(Rect::new(0, 0, 5, 5));
+        let touching = Window::new(Rect::new(5, 0, 5, 5));
+        let overlapping = Window::new(Rect::new(4, 4, 5, 5));
+        assert!(!a.overlaps(&touching));
+        assert!(a.overlaps(&overlapping));
+        assert!(overlapping.overlaps(&a));
+    }
+
+    #[test]
+    fn resize_respects_size_limits() {
+        let mut win = Window::new(Rect::new(0, 0, 6, 5));
+        for _ in 0..10 {
+            win.resize_by(-1, -1);
+            let rect = win.rect();
+            assert!(rect.width >= 3 && rect.height >= 3);
+        }
+        assert_eq!((win.rect().width, win.rect().height), (3, 3));
+        win.draw_border("title");
+
+        win.set_max_size(8, 4);
+        win.resize(20, 20);
+        assert_eq!((win.rect().width, win.rect().height), (8, 4));
+        win.set_min_size(1, 1);
+        win.resize(1, 2);
+        assert_eq!((win.rect().width, win.rect().height), (1, 2));
+    }
+
+    #[test]
+    fn draw_border_on_tiny_windows() {
+        for (width, height) in [(0, 0), (1, 1), (2, 2), (2, 10), (10, 1)] {
+            let mut win = Window::new(Rect::new(0, 0, width, height));
+            win.draw_border("title");
+            win.set_border_style(Style::default().fg(Color::Red));
+            assert_eq!(win.title(), Some("title"));
+        }
+
+        let mut win = Window::new(Rect::new(0, 0, 2, 2));
+        win.draw_border("title");
+        assert_eq!(win.buffer.to_lines(), vec!["╭╮", "╰╯"]);
+    }
+
+    #[test]
+    fn event_variants() {
+        use crossterm::event::{KeyCode, KeyModifiers, MouseEventKind};
+
+        let mut win = Window::new(Rect::new(0, 0, 10, 5));
+        let key = Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        let mouse = Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 1,
+            row: 2,
+            modifiers: KeyModifiers::NONE,
+        });
+        for event in [key, mouse, Event::Focus(false), Event::Focus(true)] {
+            win.event(event);
+        }
+        win.resize(12, 5);
+        win.resize(14, 6);
+        win.resize(14, 6);
+        win.event(Event::Tick);
+        win.event(Event::Resize(3, 3));
+
+        assert_eq!(
+            win.take_events(),
+            vec![
+                key,
+                mouse,
+                Event::Focus(true),
+                Event::Resize(14, 6),
+                Event::Tick,
+                Event::Resize(3, 3),
+            ]
+        );
+    }
+
+    #[derive(Debug)]
+    struct Fill;
+
+    impl Widget for Fill {
+        fn render(&self, buf: &mut Buffer, area: Rect) {
+            let mut cell = Cell::default();
+            cell.set_symbol("X");
+            buf.fill(area, &cell);
+        }
+
+        fn on_event(&mut self, event: Event) -> bool {
+            event == Event::Tick
+        }
+    }
+
+    #[test]
+    fn widget_renders_into_content_area() {
+        let mut win = Window::new(Rect::new(2, 2, 4, 3));
+        win.set_widget(Fill);
+        win.render_widget();
+        assert_eq!(win.buffer.to_lines(), vec!["XXXX"; 3]);
+
+        win.draw_border("");
+        win.render_widget();
+        assert_eq!(win.buffer.to_lines(), vec!["╭──╮", "│XX│", "╰──╯"]);
+
+        win.event(Event::Tick);
+        win.event(Event::Focus(true));
+        assert_eq!(win.take_events(), vec![Event::Focus(true)]);
+        assert!(win.clone().widget().is_none());
+        assert!(win.take_widget().is_some());
+    }
+
+    #[test]
+    fn horizontal_scroll() {
+        let text = "abcdefghijklmnopqrst";
+        let mut win = Window::new(Rect::new(0, 0, 10, 3));
+        win.set_content_size(20, 3);
+        win.set_string(0, 1, text, Style::default());
+
+        win.scroll_to(5, 0);
+        let visible: String = win
+            .content_iter()
+            .filter(|&(_, y, _)| y == 1)
+            .map(|(_, _, cell)| cell.symbol.as_str())
+            .collect();
+        assert_eq!(visible, &text[5..15]);
+
+        let mut target = Buffer::empty(10, 3);
+        win.draw(&mut target);
+        assert_eq!(target.to_lines()[1], &text[5..15]);
+
+        win.scroll_by(20, 5);
+        assert_eq!(win.scroll(), (10, 0));
+        win.scroll_by(-3, -1);
+        assert_eq!(win.scroll(), (7, 0));
+    }
+
+    #[test]
+    fn rect_split() {
+        let columns = Rect::new(2, 1, 10, 4).split_horizontal(3);
+        assert_eq!(
+            columns,
+            vec![
+                Rect::new(2, 1, 4, 4),
+                Rect::new(6, 1, 3, 4),
+                Rect::new(9, 1, 3, 4),
+            ]
+        );
+        for pair in columns.windows(2) {
+            assert_eq!(pair[0].right(), pair[1].left());
+        }
+
+        let rows = Rect::new(0, 0, 5, 7).split_vertical(4);
+        assert_eq!(
+            rows.iter().map(|r| (r.y, r.height)).collect::<Vec<_>>(),
+            vec![(0, 2), (2, 2), (4, 2), (6, 1)]
+        );
+        assert!(Rect::new(0, 0, 5, 5).split_vertical(0).is_empty());
+
+        let rect = Rect::new(1, 1, 10, 5);
+        assert_eq!(
+            rect.split_at_ratio(0.25, Axis::Horizontal),
+            (Rect::new(1, 1, 3, 5), Rect::new(4, 1, 7, 5))
+        );
+        assert_eq!(
+            rect.split_at_ratio(2.0, Axis::Vertical),
+            (rect, Rect::new(1, 6, 10, 0))
+        );
+    }
 }
@@ -0,0 +1,1805 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::queue;
+use crossterm::terminal::{Clear, ClearType, ScrollDown, ScrollUp};
+
+use crate::backend::{Backend, CrosstermBackend};
+use crate::buffer::Buffer;
+use crate::clock::{Clock, SystemClock};
+use crate::keymap::{Action, KeyMap};
+use crate::render::{self, RenderOptions};
+use crate::text::{Modifier, Style};
+use crate::theme::Theme;
+use crate::window::{self, Rect, Window, WindowError};
+
+/// Owns a stack of windows and composites them onto the screen.
+///
+/// Windows are drawn in the order they were added, so the last window ends up on top. Two
+/// buffers are kept: the one being composited and the one last flushed to the screen, so only
+/// the cells that changed between frames are emitted.
+#[derive(Debug)]
+pub struct WindowManager<B: Backend = CrosstermBackend> {
+    backend: B,
+    clock: Box<dyn Clock>,
+    windows: Vec<Window>,
+    buffers: [Buffer; 2],
+    current: usize,
+    current_window: usize,
+    should_exit: bool,
+    debug: bool,
+    frame: u64,
+    render_on_idle: bool,
+    frame_time: Duration,
+    theme: Theme,
+    drag: Option<Drag>,
+    /// Whether something other than a window changed the screen since the last composite.
+    redraw: bool,
+    /// Whether the next flush clears the screen first, since what is on it no longer matches
+    /// the last frame.
+    clear_screen: bool,
+    show_resize_handles: bool,
+    /// The window under the mouse.
+    hovered: Option<usize>,
+    /// The output of the last flush, only kept when `capture_frames` is set.
+    last_frame: Option<Vec<u8>>,
+    /// The output of the current flush, written to the backend in one go. Kept to reuse its
+    /// allocation from frame to frame.
+    out: Vec<u8>,
+    /// Whether the last flush left the terminal cursor shown.
+    cursor_shown: bool,
+    render_options: RenderOptions,
+    keymap: KeyMap,
+}
+
+impl WindowManager {
+    pub fn new(width: u16, height: u16) -> Self {
+        WindowManager::with_backend(CrosstermBackend::new(), width, height)
+    }
+
+    pub fn init() -> Self {
+        let (width, height) = crossterm::terminal::size().expect("Unable to get terminal size");
+        WindowManager::new(width, height)
+    }
+}
+
+impl<B: Backend> WindowManager<B> {
+    pub fn with_backend(backend: B, width: u16, height: u16) -> Self {
+        let render_on_idle = backend.is_tty();
+        WindowManager {
+            backend,
+            clock: Box::new(SystemClock),
+            windows: Vec::new(),
+            buffers: [Buffer::empty(width, height), Buffer::empty(width, height)],
+            current: 0,
+            current_window: 0,
+            should_exit: false,
+            debug: false,
+            frame: 0,
+            render_on_idle,
+            frame_time: Duration::from_millis(50),
+            theme: Theme::default(),
+            drag: None,
+            redraw: true,
+            clear_screen: false,
+            show_resize_handles: false,
+            hovered: None,
+            last_frame: None,
+            out: Vec::new(),
+            cursor_shown: false,
+            render_options: RenderOptions::default(),
+            keymap: KeyMap::default(),
+        }
+    }
+
+    /// Sets the time between frames drawn by `run`, which defaults to 50ms.
+    pub fn with_frame_time(mut self, frame_time: Duration) -> Self {
+        self.frame_time = frame_time;
+        self
+    }
+
+    pub fn set_frame_time(&mut self, frame_time: Duration) {
+        self.frame_time = frame_time;
+    }
+
+    /// Resizes the screen buffers to the new terminal size. Both are cleared, since their old
+    /// contents no longer line up with the screen, and every window is redrawn on the next frame.
+    /// So is the screen itself, or the terminal would keep showing glyphs the new frame doesn't
+    /// overwrite.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        for buffer in &mut self.buffers {
+            buffer.resize(width, height);
+            buffer.reset();
+        }
+        self.redraw = true;
+        self.clear_screen = true;
+    }
+
+    /// Returns when the frame after one started at `frame_start` is due.
+    fn next_frame(&self, frame_start: Instant) -> Instant {
+        frame_start + self.frame_time
+    }
+
+    /// Draws a frame if `next_frame` has come and schedules the next one relative to the start
+    /// of this frame, so the time spent drawing doesn't slow down the frame rate. Otherwise
+    /// returns how long is left until the frame is due.
+    fn pace_frame(&mut self, next_frame: &mut Instant) -> io::Result<Option<Duration>> {
+        let frame_start = self.clock.now();
+        if frame_start < *next_frame {
+            return Ok(Some(*next_frame - frame_start));
+        }
+        self.tick()?;
+        *next_frame = self.next_frame(frame_start);
+        Ok(None)
+    }
+
+    /// Replaces the clock used for timing frames and window updates.
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Box::new(clock);
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Adds a window on top of all others and returns its index. Windows without a theme of
+    /// their own get the manager's theme.
+    pub fn add_window(&mut self, mut window: Window) -> usize {
+        if !window.has_theme() {
+            window.set_theme(&self.theme);
+        }
+        self.windows.push(window);
+        self.redraw = true;
+        self.windows.len() - 1
+    }
+
+    /// Adds a window made from `template` with `Window::template`, placed at `area`, and
+    /// returns its index.
+    pub fn spawn_from(&mut self, template: &Window, area: Rect) -> usize {
+        let mut window = template.template();
+        window.move_to(area.x, area.y);
+        window.resize(area.width, area.height);
+        window.reset();
+        window.redraw_border();
+        self.add_window(window)
+    }
+
+    /// Like `add_window`, but refuses windows that do not fit on the screen.
+    pub fn try_add_window(&mut self, window: Window) -> Result<usize, WindowError> {
+        let screen = &self.buffers[self.current];
+        let rect = window.rect();
+        let fits = rect.x as u32 + rect.width as u32 <= screen.width as u32
+            && rect.y as u32 + rect.height as u32 <= screen.height as u32;
+        if !fits {
+            return Err(WindowError::OutOfBounds(rect));
+        }
+        Ok(self.add_window(window))
+    }
+
+    /// Removes the window at `index` and returns it, or `None` if there is no such window.
+    ///
+    /// Focus stays on the same window if it was another one, otherwise it moves to the window
+    /// that took the closed one's place, or the new top window if the top one was closed.
+    pub fn close_window(&mut self, index: usize) -> Option<Window> {
+        if index >= self.windows.len() {
+            return None;
+        }
+        let window = self.windows.remove(index);
+        let was_focused = self.current_window == index;
+        if self.current_window > index {
+            self.current_window -= 1;
+        }
+        self.current_window = self
+            .current_window
+            .min(self.windows.len().saturating_sub(1));
+        self.hovered = match self.hovered {
+            Some(hovered) if hovered == index => None,
+            Some(hovered) if hovered > index => Some(hovered - 1),
+            hovered => hovered,
+        };
+        self.drag = None;
+        self.redraw = true;
+        if was_focused {
+            if let Some(win) = self.windows.get_mut(self.current_window) {
+                win.event(window::Event::Focus(true));
+            }
+        }
+        Some(window)
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Switches every window to `theme`, redrawing their borders for the next frame.
+    ///
+    /// Content drawn by the application is left alone; it can be redrawn with the styles
+    /// from `Window::theme`.
+    pub fn apply_theme(&mut self, theme: &Theme) {
+        self.theme = *theme;
+        for win in &mut self.windows {
+            win.set_theme(theme);
+        }
+    }
+
+    pub fn windows(&self) -> &[Window] {
+        &self.windows
+    }
+
+    pub fn window(&self, index: usize) -> Option<&Window> {
+        self.windows.get(index)
+    }
+
+    pub fn window_mut(&mut self, index: usize) -> Option<&mut Window> {
+        self.windows.get_mut(index)
+    }
+
+    /// Returns the parts of a window's area that are not covered by any window above it.
+    /// Minimized windows cover nothing.
+    pub fn visible_region(&self, index: usize) -> Vec<Rect> {
+        let Some(win) = self.windows.get(index) else {
+            return Vec::new();
+        };
+
+        let mut region = vec![win.rect()];
+        for above in self.windows[index + 1..]
+            .iter()
+            .filter(|above| !above.is_minimized())
+        {
+            let hole = above.rect();
+            region = region
+                .into_iter()
+                .flat_map(|rect| subtract(rect, hole))
+                .collect();
+        }
+        region
+    }
+
+    /// Returns the indices of the other windows overlapping the window at `index`, leaving out
+    /// minimized ones.
+    pub fn windows_overlapping(&self, index: usize) -> Vec<usize> {
+        let Some(win) = self.windows.get(index) else {
+            return Vec::new();
+        };
+        self.windows
+            .iter()
+            .enumerate()
+            .filter(|&(i, other)| i != index && !other.is_minimized() && win.overlaps(other))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns the index of the topmost window containing the point.
+    pub fn window_at(&self, x: u16, y: u16) -> Option<usize> {
+        self.windows
+            .iter()
+            .rposition(|win| !win.is_minimized() && win.rect().contains(x, y))
+    }
+
+    pub fn focused_window(&self) -> Option<&Window> {
+        self.windows.get(self.current_window)
+    }
+
+    /// Focuses the window at `index`, returning `false` if there is no such window.
+    pub fn focus_window(&mut self, index: usize) -> bool {
+        if index >= self.windows.len() {
+            return false;
+        }
+        self.set_focus(index);
+        true
+    }
+
+    /// Focuses the next window that isn't minimized, wrapping around.
+    pub fn focus_next(&mut self) {
+        self.cycle_focus(true);
+    }
+
+    /// Focuses the previous window that isn't minimized, wrapping around.
+    pub fn focus_previous(&mut self) {
+        self.cycle_focus(false);
+    }
+
+    fn cycle_focus(&mut self, forward: bool) {
+        let len = self.windows.len();
+        let current = self.current_window;
+        let index = (1..=len)
+            .map(|n| match forward {
+                true => (current + n) % len,
+                false => (current % len + len - n) % len,
+            })
+            .find(|&i| !self.windows[i].is_minimized());
+        if let Some(index) = index {
+            self.set_focus(index);
+        }
+    }
+
+    /// Moves the focus to `index`, letting both windows know with an `Event::Focus`.
+    fn set_focus(&mut self, index: usize) {
+        let previous = std::mem::replace(&mut self.current_window, index);
+        self.redraw = true;
+        if previous == index {
+            return;
+        }
+        if let Some(win) = self.windows.get_mut(previous) {
+            win.event(window::Event::Focus(false));
+        }
+        self.windows[index].event(window::Event::Focus(true));
+    }
+
+    pub fn should_exit(&self) -> bool {
+        self.should_exit
+    }
+
+    /// Shows an overlay with the frame count and number of windows in the top right corner.
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = debug;
+        self.redraw = true;
+    }
+
+    /// Whether `tick` redraws the screen. This defaults to whether the backend is a tty, so
+    /// output redirected to a file is only written on an explicit `update_screen`.
+    pub fn set_render_on_idle(&mut self, render_on_idle: bool) {
+        self.render_on_idle = render_on_idle;
+    }
+
+    /// Leaves cursor moves out of the output, so a frame written to a file reads as plain text
+    /// with one line per row that changed. Off by default, since the cells then no longer end
+    /// up in the right place on a terminal.
+    pub fn set_strip_cursor_moves(&mut self, strip: bool) {
+        self.render_options.strip_cursor_moves = strip;
+    }
+
+    /// Shows a handle at the bottom-right corner of the focused window, which can be dragged
+    /// with the mouse to resize it.
+    pub fn set_show_resize_handles(&mut self, show: bool) {
+        self.show_resize_handles = show;
+        self.redraw = true;
+    }
+
+    /// Returns the screen position of the focused window's resize handle, if it is shown. It
+    /// isn't while another window covers the corner.
+    fn resize_handle(&self) -> Option<(u16, u16)> {
+        if !self.show_resize_handles {
+            return None;
+        }
+        let win = self.focused_window().filter(|win| !win.is_minimized())?;
+        let rect = win.rect();
+        let screen = &self.buffers[self.current];
+        let (x, y) = (rect.right().checked_sub(1)?, rect.bottom().checked_sub(1)?);
+        let visible = x < screen.width
+            && y < screen.height
+            && self.window_at(x, y) == Some(self.current_window);
+        visible.then_some((x, y))
+    }
+
+    /// Replaces the window management keybinds, see `handle_manager_keys` for the defaults.
+    pub fn set_keymap(&mut self, keymap: KeyMap) {
+        self.keymap = keymap;
+    }
+
+    pub fn keymap(&self) -> &KeyMap {
+        &self.keymap
+    }
+
+    /// Draws box drawing characters, such as window borders, and a few common symbols as ASCII
+    /// for terminals that can't display them.
+    pub fn set_ascii(&mut self, enabled: bool) {
+        if self.render_options.ascii == enabled {
+            return;
+        }
+        self.render_options.ascii = enabled;
+        // The cells on screen don't change, only how they are written, so forget the last frame
+        // and clear the screen to have all of them written again.
+        self.buffers[1 - self.current].reset();
+        self.redraw = true;
+        self.clear_screen = true;
+    }
+
+    /// Keeps a copy of the bytes written by each flush, see `last_frame_ansi`.
+    pub fn set_capture_frames(&mut self, capture: bool) {
+        self.last_frame = capture.then(Vec::new);
+    }
+
+    /// Returns the escape sequences written by the last flush, or an empty string when frames
+    /// aren't captured.
+    pub fn last_frame_ansi(&self) -> String {
+        self.last_frame
+            .as_deref()
+            .map(String::from_utf8_lossy)
+            .unwrap_or_default()
+            .into_owned()
+    }
+
+    /// Composites all windows into the current buffer.
+    pub fn draw_windows(&mut self) {
+        let handle = self.resize_handle();
+        for win in &mut self.windows {
+            win.render_widget();
+        }
+        let buffer = &mut self.buffers[self.current];
+        buffer.reset();
+        composite(&self.windows, buffer);
+        for win in &mut self.windows {
+            win.clear_dirty();
+        }
+        self.redraw = false;
+
+        if let Some((x, y)) = handle {
+            buffer[(x, y)].set_symbol(RESIZE_HANDLE);
+        }
+
+        if self.debug {
+            let info = format!(" frame {} | {} windows ", self.frame, self.windows.len());
+            let x = buffer.width.saturating_sub(info.len() as u16);
+            buffer.set_string(
+                x,
+                0,
+                info,
+                Style::default().add_modifier(Modifier::REVERSED),
+            );
+        }
+        buffer.hash_rows();
+    }
+
+    /// Composites all windows into a new buffer without any overlays, leaving the screen and the
+    /// manager's own buffers untouched.
+    pub fn screenshot(&self) -> Buffer {
+        let current = &self.buffers[self.current];
+        let mut buffer = Buffer::empty(current.width, current.height);
+        self.composite_into(&mut buffer);
+        buffer
+    }
+
+    /// Draws all windows into `target` from the bottom up, e.g. for a render loop that manages
+    /// its own buffers. Cells of `target` not covered by a window are left as they are.
+    pub fn composite_into(&self, target: &mut Buffer) {
+        composite(&self.windows, target);
+    }
+
+    /// Writes the difference between the last flushed frame and the current buffer.
+    ///
+    /// The terminal cursor is hidden while drawing and then moved to the focused window's
+    /// cursor, if it has one.
+    pub fn draw<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let strip = self.render_options.strip_cursor_moves;
+        if self.cursor_shown && !strip {
+            queue!(writer, Hide)?;
+        }
+        if self.clear_screen {
+            queue!(writer, Clear(ClearType::All))?;
+        }
+        Self::draw_frame(
+            &mut writer,
+            &self.buffers[1 - self.current],
+            &self.buffers[self.current],
+            self.render_options,
+        )?;
+        match self.cursor_position() {
+            Some((x, y)) if !strip => queue!(writer, MoveTo(x, y), Show),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the screen position of the focused window's cursor, if it has one that is
+    /// visible on the screen.
+    pub fn cursor_position(&self) -> Option<(u16, u16)> {
+        let win = self.focused_window().filter(|win| !win.is_minimized())?;
+        let (x, y) = win.cursor()?;
+        let rect = win.rect();
+        let (x, y) = (rect.x.checked_add(x)?, rect.y.checked_add(y)?);
+        let screen = &self.buffers[self.current];
+        (rect.contains(x, y) && x < screen.width && y < screen.height).then_some((x, y))
+    }
+
+    /// Writes the difference between two frames, scrolling the terminal first when the new
+    /// frame is the old one shifted vertically, so only the newly exposed rows are repainted.
+    fn draw_frame<W>(
+        mut writer: W,
+        previous: &Buffer,
+        current: &Buffer,
+        options: RenderOptions,
+    ) -> io::Result<()>
+    where
+        W: Write,
+    {
+        // Scrolling the terminal is a cursor move as far as stripping them goes.
+        let scrolled = (!options.strip_cursor_moves)
+            .then(|| scrolled_frame(previous, current))
+            .flatten();
+        if let Some((scrolled, offset)) = scrolled {
+            match offset {
+                n if n > 0 => queue!(writer, ScrollUp(n as u16))?,
+                n => queue!(writer, ScrollDown(n.unsigned_abs() as u16))?,
+            }
+            return Self::draw_diff(writer, &scrolled, current, options);
+        }
+        Self::draw_diff(writer, previous, current, options)
+    }
+
+    fn draw_diff<W>(
+        writer: W,
+        previous: &Buffer,
+        current: &Buffer,
+        options: RenderOptions,
+    ) -> io::Result<()>
+    where
+        W: Write,
+    {
+        render::render_cells(writer, previous.diff(current), options)
+    }
+
+    /// Writes the current frame to the backend and swaps the buffers, leaving a blank buffer for
+    /// the next frame.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let mut out = std::mem::take(&mut self.out);
+        out.clear();
+        let written = self.draw(&mut out).and_then(|()| {
+            let writer = self.backend.writer();
+            writer.write_all(&out)?;
+            writer.flush()
+        });
+        self.out = out;
+        written?;
+        if let Some(frame) = &mut self.last_frame {
+            std::mem::swap(frame, &mut self.out);
+        }
+        self.cursor_shown = self.cursor_position().is_some();
+        self.clear_screen = false;
+        self.frame += 1;
+        self.current = 1 - self.current;
+        self.buffers[self.current].reset();
+        Ok(())
+    }
+
+    /// Composites all windows and flushes the result to the backend, unless nothing changed
+    /// since the last time.
+    pub fn update_screen(&mut self) -> io::Result<()> {
+        if !self.needs_redraw() {
+            return Ok(());
+        }
+        self.draw_windows();
+        self.flush()
+    }
+
+    /// Whether a window changed or an event was handled since the windows were last
+    /// composited. The debug overlay changes every frame, so it always needs a redraw.
+    pub fn needs_redraw(&self) -> bool {
+        self.redraw || self.debug || self.windows.iter().any(Window::is_dirty)
+    }
+
+    /// Runs a single idle frame of the event loop, updating the windows that are due.
+    pub fn tick(&mut self) -> io::Result<()> {
+        let now = self.clock.now();
+        for win in &mut self.windows {
+            win.update_at(now);
+        }
+        if self.render_on_idle {
+            self.update_screen()?;
+        }
+        Ok(())
+    }
+
+    /// Routes a key event, handling the window management keybinds and forwarding other keys
+    /// to the focused window.
+    ///
+    /// Releases are ignored, and repeats are only forwarded to windows that opted in with
+    /// `Window::set_key_repeat`. Window management keys act on presses only, so holding e.g.
+    /// `Ctrl+w` closes a single window.
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.kind == KeyEventKind::Release || self.handle_manager_keys(key) {
+            return;
+        }
+        if let Some(win) = self.windows.get_mut(self.current_window) {
+            if key.kind == KeyEventKind::Press || win.key_repeat() {
+                win.event(window::Event::Key(key));
+            }
+        }
+    }
+
+    /// Handles the window management keybinds, returning `true` if the key was consumed.
+    ///
+    /// The keybinds can be changed with `set_keymap`, by default:
+    ///
+    /// - `Ctrl+c` exits
+    /// - `Ctrl+arrows` move the focused window
+    /// - `Ctrl+Shift+arrows` resize the focused window
+    /// - `PageUp` / `PageDown` raise / lower the focused window
+    /// - `Ctrl+z` minimizes / restores the focused window
+    /// - `Ctrl+w` closes the focused window
+    /// - `Tab` / `Shift+Tab` focus the next / previous window that isn't minimized
+    pub fn handle_manager_keys(&mut self, key: KeyEvent) -> bool {
+        let Some(action) = self.keymap.action(key) else {
+            return false;
+        };
+        // Repeats and releases of a bound key are consumed without acting again.
+        if key.kind != KeyEventKind::Press {
+            return true;
+        }
+
+        match action {
+            Action::Quit => {
+                self.should_exit = true;
+                return true;
+            }
+            Action::CycleNext => {
+                self.focus_next();
+                return true;
+            }
+            Action::CyclePrevious => {
+                self.focus_previous();
+                return true;
+            }
+            _ => {}
+        }
+
+        let index = self.current_window;
+        if index >= self.windows.len() {
+            return false;
+        }
+
+        match action {
+            Action::MoveLeft => self.windows[index].move_by(-1, 0),
+            Action::MoveRight => self.windows[index].move_by(1, 0),
+            Action::MoveUp => self.windows[index].move_by(0, -1),
+            Action::MoveDown => self.windows[index].move_by(0, 1),
+            Action::ResizeLeft => self.windows[index].resize_by(-1, 0),
+            Action::ResizeRight => self.windows[index].resize_by(1, 0),
+            Action::ResizeUp => self.windows[index].resize_by(0, -1),
+            Action::ResizeDown => self.windows[index].resize_by(0, 1),
+            Action::Close => {
+                self.close_window(index);
+            }
+            Action::ToggleMinimize => {
+                let win = &mut self.windows[index];
+                if win.is_minimized() {
+                    win.restore();
+                } else {
+                    win.minimize();
+                }
+            }
+            Action::Raise if index + 1 < self.windows.len() => self.swap_windows(index, index + 1),
+            Action::Lower if index > 0 => self.swap_windows(index, index - 1),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Swaps the windows at `a` and `b` in the stack. The focused, hovered and dragged windows
+    /// stay the same, whatever their new index.
+    fn swap_windows(&mut self, a: usize, b: usize) {
+        let swapped = |index: usize| {
+            if index == a {
+                b
+            } else if index == b {
+                a
+            } else {
+                index
+            }
+        };
+        self.windows.swap(a, b);
+        self.current_window = swapped(self.current_window);
+        self.hovered = self.hovered.map(swapped);
+        self.drag = match self.drag {
+            Some(Drag::Move { index, dx, dy }) => Some(Drag::Move {
+                index: swapped(index),
+                dx,
+                dy,
+            }),
+            Some(Drag::Resize { index }) => Some(Drag::Resize {
+                index: swapped(index),
+            }),
+            None => None,
+        };
+        self.redraw = true;
+    }
+
+    /// Routes a mouse event to the topmost window under the cursor.
+    ///
+    /// Pressing a button focuses the window, dragging a window by its top row moves it and
+    /// dragging the resize handle resizes it. Other events are forwarded to the window with
+    /// window-local coordinates.
+    pub fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let (x, y) = (mouse.column, mouse.row);
+        match (mouse.kind, self.drag) {
+            (MouseEventKind::Drag(MouseButton::Left), Some(Drag::Move { index, dx, dy })) => {
+                self.windows[index].move_to(x.saturating_sub(dx), y.saturating_sub(dy));
+                return;
+            }
+            (MouseEventKind::Drag(MouseButton::Left), Some(Drag::Resize { index })) => {
+                let win = &mut self.windows[index];
+                let (win_x, win_y) = win.pos();
+                let width = x.saturating_sub(win_x).max(1) + 1;
+                let height = y.saturating_sub(win_y).max(1) + 1;
+                win.resize(width, height);
+                return;
+            }
+            (MouseEventKind::Up(MouseButton::Left), Some(_)) => {
+                self.drag = None;
+                return;
+            }
+            (MouseEventKind::Down(MouseButton::Left), _)
+                if self.resize_handle() == Some((x, y)) =>
+            {
+                self.drag = Some(Drag::Resize {
+                    index: self.current_window,
+                });
+                return;
+            }
+            _ => {}
+        }
+
+        let target = self.window_at(x, y);
+        if mouse.kind == MouseEventKind::Moved {
+            return self.hover(target, x, y);
+        }
+        let Some(index) = target else {
+            return;
+        };
+        let (win_x, win_y) = self.windows[index].pos();
+        let local = MouseEvent {
+            column: x - win_x,
+            row: y - win_y,
+            ..mouse
+        };
+
+        if let MouseEventKind::Down(button) = mouse.kind {
+            self.focus_window(index);
+            if button == MouseButton::Left && local.row == 0 {
+                self.drag = Some(Drag::Move {
+                    index,
+                    dx: local.column,
+                    dy: local.row,
+                });
+                return;
+            }
+        }
+        self.windows[index].event(window::Event::Mouse(local));
+    }
+
+    /// Moves the hover to the window at `index`, sending it an `Event::Hover` when the mouse
+    /// entered a different cell.
+    fn hover(&mut self, index: Option<usize>, x: u16, y: u16) {
+        if let Some(previous) = self.hovered.filter(|&previous| Some(previous) != index) {
+            if let Some(win) = self.windows.get_mut(previous) {
+                win.set_hovered(None);
+            }
+        }
+        self.hovered = index;
+
+        let Some(win) = index.map(|index| &mut self.windows[index]) else {
+            return;
+        };
+        let (win_x, win_y) = win.pos();
+        let local = (x - win_x, y - win_y);
+        if win.hovered() != Some(local) {
+            win.set_hovered(Some(local));
+            win.event(window::Event::Hover(local));
+        }
+    }
+
+    /// Returns the index of the window under the mouse.
+    pub fn hovered_window(&self) -> Option<usize> {
+        self.hovered
+    }
+
+    /// Runs the event loop until `Ctrl+c` is pressed, redrawing every frame.
+    ///
+    /// Keys are routed with `handle_key` and mouse events with `handle_mouse`.
+    pub fn run(&mut self) -> io::Result<()> {
+        let mut next_frame = self.clock.now();
+        while !self.should_exit {
+            let Some(timeout) = self.pace_frame(&mut next_frame)? else {
+                continue;
+            };
+            if !event::poll(timeout)? {
+                continue;
+            }
+            let event = event::read()?;
+            self.redraw = true;
+            match event {
+                event::Event::Key(key) => self.handle_key(key),
+                event::Event::Mouse(mouse) => self.handle_mouse(mouse),
+                event::Event::Resize(width, height) => self.resize(width, height),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Draws `windows` into `target` in order, skipping those entirely outside of it.
+fn composite(windows: &[Window], target: &mut Buffer) {
+    let screen = Rect::new(0, 0, target.width, target.height);
+    for win in windows {
+        if !win.is_minimized() && !win.rect().intersection(screen).is_empty() {
+            win.draw(target);
+        }
+    }
+}
+
+/// Drawn over the bottom-right corner of the focused window when resize handles are shown.
+const RESIZE_HANDLE: &str = "◢";
+
+/// A window being moved or resized with the mouse.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Drag {
+    /// Moving the window by its top row, grabbed at the window-local offset `(dx, dy)`.
+    Move { index: usize, dx: u16, dy: u16 },
+    /// Resizing the window by its bottom-right corner.
+    Resize { index: usize },
+}
+
+/// Finds a vertical shift of `previous` that leaves fewer cells to repaint than `current`, and
+/// returns the shifted buffer as the terminal would show it after scrolling, along with the
+/// number of rows scrolled up (negative for down).
+///
+/// Shifts are matched on row hashes, mostly cached by `Buffer::hash_rows`, so only a shift
+/// whose hashes line up has its cells compared.
+fn scrolled_frame(previous: &Buffer, current: &Buffer) -> Option<(Buffer, i32)> {
+    if previous.width != current.width || previous.height != current.height {
+        return None;
+    }
+    let width = current.width as usize;
+    let height = current.height as usize;
+    let hashes = |buffer: &Buffer| {
+        (0..current.height)
+            .map(|y| buffer.row_hash(y))
+            .collect::<Vec<_>>()
+    };
+    let (old_rows, new_rows) = (hashes(previous), hashes(current));
+
+    let offset = (1..height).find_map(|n| {
+        let kept = (height - n) * width;
+        let shift = n * width;
+        if old_rows[n..] == new_rows[..height - n]
+            && previous.content[shift..] == current.content[..kept]
+        {
+            Some(n as i32)
+        } else if old_rows[..height - n] == new_rows[n..]
+            && previous.content[..kept] == current.content[shift..]
+        {
+            Some(-(n as i32))
+        } else {
+            None
+        }
+    })?;
+
+    let mut scrolled = previous.clone();
+    if offset > 0 {
+        scrolled.scroll_up(offset as u16);
+    } else {
+        scrolled.scroll_down(offset.unsigned_abs() as u16);
+    }
+    let repaint = scrolled.diff(current).count();
+    (repaint < previous.diff(current).count()).then_some((scrolled, offset))
+}
+
+/// Splits `rect` into the (up to four) rects left over after removing `hole`.
+fn subtract(rect: Rect, hole: Rect) -> Vec<Rect> {
+    let overlap = rect.intersection(hole);
+    if overlap.is_empty() {
+        return vec![rect];
+    }
+
+    [
+        Rect::new(rect.x, rect.y, rect.width, overlap.y - rect.y),
+        Rect::new(
+            rect.x,
+            overlap.bottom(),
+            rect.width,
+            rect.bottom() - overlap.bottom(),
+        ),
+        Rect::new(rect.x, overlap.y, overlap.x - rect.x, overlap.height),
+        Rect::new(
+            overlap.right(),
+            overlap.y,
+            rect.right() - overlap.right(),
+            overlap.height,
+        ),
+    ]
+    .into_iter()
+    .filter(|r| !r.is_empty())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Cell;
+    use crate::text::Color;
+    use crate::window::Rect;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn draw_and_flush_to_writer() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 10);
+        let mut win = Window::new(Rect::new(2, 1, 6, 3));
+        win.set_string(0, 0, "hello", Style::default());
+        manager.add_window(win);
+
+        manager.draw_windows();
+        manager.flush().unwrap();
+
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        assert_eq!(out, "\x1b[2;3Hhello\x1b[39m\x1b[49m\x1b[0m");
+    }
+
+    #[test]
+    fn flush_only_writes_changes() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 10);
+        let win = manager.add_window(Window::new(Rect::new(0, 0, 6, 3)));
+        manager.update_screen().unwrap();
+        manager.backend_mut().clear();
+
+        let style = Style::default().fg(crossterm::style::Color::Red);
+        manager
+            .window_mut(win)
+            .unwrap()
+            .set_string(1, 1, "a", style);
+        manager.update_screen().unwrap();
+
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        assert_eq!(out, "\x1b[2;2H\x1b[38;5;9ma\x1b[39m\x1b[49m\x1b[0m");
+    }
+
+    #[test]
+    fn visible_region_excludes_higher_windows() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let lower = manager.add_window(Window::new(Rect::new(2, 2, 10, 8)));
+        manager.add_window(Window::new(Rect::new(0, 0, 20, 6)));
+
+        assert_eq!(manager.visible_region(lower), vec![Rect::new(2, 6, 10, 4)]);
+        assert_eq!(
+            manager.visible_region(lower + 1),
+            vec![Rect::new(0, 0, 20, 6)]
+        );
+    }
+
+    #[test]
+    fn screenshot_excludes_debug_overlay() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 10);
+        let mut win = Window::new(Rect::new(1, 1, 8, 2));
+        win.set_string(0, 0, "content", Style::default());
+        manager.add_window(win.clone());
+        manager.set_debug(true);
+        manager.draw_windows();
+
+        let mut expected = Buffer::empty(40, 10);
+        win.draw(&mut expected);
+
+        let screenshot = manager.screenshot();
+        assert_eq!(screenshot, expected);
+        assert_ne!(screenshot, manager.buffers[manager.current]);
+    }
+
+    #[test]
+    fn idle_ticks_without_tty() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 10);
+        let mut win = Window::new(Rect::new(0, 0, 6, 3));
+        win.set_string(0, 0, "hello", Style::default());
+        manager.add_window(win);
+
+        for _ in 0..5 {
+            manager.tick().unwrap();
+        }
+        assert!(manager.backend().is_empty());
+
+        manager.update_screen().unwrap();
+        assert!(!manager.backend().is_empty());
+
+        manager.backend_mut().clear();
+        manager.set_render_on_idle(true);
+        manager
+            .window_mut(0)
+            .unwrap()
+            .set_string(0, 1, "x", Style::default());
+        manager.tick().unwrap();
+        assert!(!manager.backend().is_empty());
+    }
+
+    #[test]
+    fn strip_cursor_moves() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 10);
+        manager.set_strip_cursor_moves(true);
+        let mut win = Window::new(Rect::new(2, 1, 6, 3));
+        win.set_string(0, 0, "hello", Style::default());
+        win.set_string(0, 2, "world", Style::default());
+        win.set_cursor(0, 1);
+        manager.add_window(win);
+
+        manager.update_screen().unwrap();
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        assert_eq!(out, "hello\nworld\x1b[39m\x1b[49m\x1b[0m");
+    }
+
+    #[test]
+    fn tab_cycles_focus() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        for i in 0..3 {
+            manager.add_window(Window::new(Rect::new(i * 5, 0, 4, 4)));
+        }
+        let tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        let back_tab = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+
+        let focused = |m: &WindowManager<Vec<u8>>| m.focused_window().map(|w| w.pos());
+        assert_eq!(focused(&manager), Some((0, 0)));
+        assert!(manager.handle_manager_keys(tab));
+        assert_eq!(focused(&manager), Some((5, 0)));
+        manager.handle_manager_keys(tab);
+        manager.handle_manager_keys(tab);
+        assert_eq!(focused(&manager), Some((0, 0)));
+        assert!(manager.handle_manager_keys(back_tab));
+        assert_eq!(focused(&manager), Some((10, 0)));
+
+        assert!(!manager.focus_window(3));
+        assert!(manager.focus_window(1));
+        assert_eq!(focused(&manager), Some((5, 0)));
+
+        // Minimized windows are skipped both ways.
+        manager.windows[2].minimize();
+        manager.handle_manager_keys(tab);
+        assert_eq!(focused(&manager), Some((0, 0)));
+        manager.handle_manager_keys(back_tab);
+        assert_eq!(focused(&manager), Some((5, 0)));
+        manager.windows[0].minimize();
+        manager.windows[1].minimize();
+        manager.handle_manager_keys(tab);
+        assert_eq!(focused(&manager), Some((5, 0)));
+    }
+
+    #[test]
+    fn tab_without_windows() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        manager.handle_manager_keys(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert!(manager.focused_window().is_none());
+        assert_eq!(manager.current_window, 0);
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn click_focuses_topmost_window() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        manager.add_window(Window::new(Rect::new(0, 0, 10, 10)));
+        manager.add_window(Window::new(Rect::new(5, 5, 10, 10)));
+        manager.add_window(Window::new(Rect::new(20, 0, 10, 10)));
+        manager.focus_window(2);
+
+        let down = MouseEventKind::Down(MouseButton::Left);
+        manager.handle_mouse(mouse(down, 7, 8));
+        assert_eq!(manager.current_window, 1);
+        let events = manager.window_mut(1).unwrap().take_events();
+        assert_eq!(
+            events,
+            vec![
+                window::Event::Focus(true),
+                window::Event::Mouse(mouse(down, 2, 3))
+            ]
+        );
+
+        manager.handle_mouse(mouse(down, 2, 2));
+        assert_eq!(manager.current_window, 0);
+
+        manager.handle_mouse(mouse(down, 35, 15));
+        assert_eq!(manager.current_window, 0);
+    }
+
+    #[test]
+    fn drag_title_row_moves_window() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        manager.add_window(Window::new(Rect::new(4, 4, 10, 5)));
+
+        manager.handle_mouse(mouse(MouseEventKind::Down(MouseButton::Left), 6, 4));
+        manager.handle_mouse(mouse(MouseEventKind::Drag(MouseButton::Left), 10, 7));
+        manager.handle_mouse(mouse(MouseEventKind::Up(MouseButton::Left), 10, 7));
+        manager.handle_mouse(mouse(MouseEventKind::Drag(MouseButton::Left), 20, 10));
+
+        assert_eq!(manager.window(0).unwrap().pos(), (8, 7));
+        assert!(manager.window_mut(0).unwrap().take_events().is_empty());
+    }
+
+    #[test]
+    fn hyperlink_wraps_linked_run_once() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 2);
+        let mut win = Window::new(Rect::new(0, 0, 10, 1));
+        win.set_string(0, 0, "see docs", Style::default());
+        manager.add_window(win);
+        manager.draw_windows();
+        let buffer = &mut manager.buffers[manager.current];
+        for x in 4..8 {
+            let i = buffer.index_of(x, 0);
+            buffer.content[i].set_hyperlink("https://example.com");
+        }
+        manager.flush().unwrap();
+
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        let open = "\x1b]8;;https://example.com\x1b\\";
+        let close = "\x1b]8;;\x1b\\";
+        assert_eq!(out.matches(open).count(), 1);
+        assert_eq!(out.matches(close).count(), 1);
+        assert!(out.contains(&format!("{open}docs{close}")));
+    }
+
+    #[test]
+    fn underline_color_only_on_change() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 2);
+        let mut win = Window::new(Rect::new(0, 0, 10, 1));
+        win.set_string(0, 0, "abcd", Style::default());
+        manager.add_window(win);
+        manager.draw_windows();
+        let buffer = &mut manager.buffers[manager.current];
+        for x in 1..3 {
+            let i = buffer.index_of(x, 0);
+            buffer.content[i].set_underline_color(crossterm::style::Color::Red);
+        }
+        manager.flush().unwrap();
+
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        let red = "\x1b[58;5;9m";
+        let reset = "\x1b[59m";
+        assert_eq!(out.matches(red).count(), 1);
+        assert_eq!(out.matches(reset).count(), 1);
+        assert!(out.contains(&format!("a{red}bc{reset}d")));
+    }
+
+    #[test]
+    fn capture_last_frame() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 5);
+        let win = manager.add_window(Window::new(Rect::new(0, 0, 10, 3)));
+        manager.update_screen().unwrap();
+        assert_eq!(manager.last_frame_ansi(), "");
+
+        manager.set_capture_frames(true);
+        manager
+            .window_mut(win)
+            .unwrap()
+            .set_string(3, 2, "hi", Style::default());
+        manager.update_screen().unwrap();
+
+        let frame = manager.last_frame_ansi();
+        assert_eq!(frame, "\x1b[3;4Hhi\x1b[39m\x1b[49m\x1b[0m");
+        assert!(manager.backend().ends_with(frame.as_bytes()));
+
+        let win = manager.window_mut(win).unwrap();
+        win.set_string(0, 0, "yo", Style::default());
+        manager.update_screen().unwrap();
+        let frame = manager.last_frame_ansi();
+        assert!(frame.contains("yo") && !frame.contains("hi"));
+    }
+
+    #[test]
+    fn throttled_window_updates() {
+        let clock = crate::clock::MockClock::new();
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 5);
+        manager.set_clock(clock.clone());
+        let fast = manager.add_window(Window::new(Rect::new(0, 0, 5, 5)));
+        let slow = manager.add_window(Window::new(Rect::new(5, 0, 5, 5)));
+        manager
+            .window_mut(slow)
+            .unwrap()
+            .set_min_update_interval(Some(Duration::from_secs(1)));
+
+        let mut updates = [0, 0];
+        for _ in 0..30 {
+            manager.tick().unwrap();
+            for (count, index) in updates.iter_mut().zip([fast, slow]) {
+                let events = manager.window_mut(index).unwrap().take_events();
+                *count += events.iter().filter(|e| **e == window::Event::Tick).count();
+            }
+            clock.advance(Duration::from_millis(50));
+        }
+
+        assert_eq!(updates, [30, 2]);
+    }
+
+    fn numbered(manager: &mut WindowManager<Vec<u8>>, first: u16) {
+        let buffer = &mut manager.buffers[manager.current];
+        for y in 0..buffer.height {
+            let symbol = char::from(b'a' + (first + y) as u8);
+            buffer.set_string(0, y, symbol.to_string().repeat(8), Style::default());
+        }
+    }
+
+    #[test]
+    fn flush_scrolls_shifted_frames() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 10, 6);
+        numbered(&mut manager, 0);
+        manager.flush().unwrap();
+        manager.backend_mut().clear();
+
+        numbered(&mut manager, 2);
+        manager.flush().unwrap();
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        assert!(out.starts_with("\x1b[2S"));
+        assert!(!out.contains("dddddddd"));
+        assert!(out.contains("\x1b[5;1Hgggggggg"));
+        assert!(out.contains("\x1b[6;1Hhhhhhhhh"));
+        manager.backend_mut().clear();
+
+        numbered(&mut manager, 1);
+        manager.flush().unwrap();
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        assert!(out.starts_with("\x1b[1T\x1b[1;1Hbbbbbbbb"));
+        assert!(!out.contains("cccccccc"));
+    }
+
+    #[test]
+    fn flush_without_shift_diffs() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 10, 6);
+        numbered(&mut manager, 0);
+        manager.flush().unwrap();
+        manager.backend_mut().clear();
+
+        numbered(&mut manager, 0);
+        let buffer = &mut manager.buffers[manager.current];
+        buffer.set_string(0, 2, "updated", Style::default());
+        manager.flush().unwrap();
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        assert_eq!(out, "\x1b[3;1Hupdated\x1b[39m\x1b[49m\x1b[0m");
+    }
+
+    #[test]
+    fn frame_time_sets_next_frame() {
+        let manager = WindowManager::with_backend(Vec::new(), 4, 2);
+        let now = Instant::now();
+        assert_eq!(manager.next_frame(now), now + Duration::from_millis(50));
+
+        let manager = manager.with_frame_time(Duration::from_millis(10));
+        assert_eq!(manager.next_frame(now), now + Duration::from_millis(10));
+    }
+
+    #[test]
+    fn try_add_window_out_of_bounds() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 10, 5);
+        let rect = Rect::new(8, 0, 4, 2);
+        assert_eq!(
+            manager.try_add_window(Window::new(rect)),
+            Err(WindowError::OutOfBounds(rect))
+        );
+        assert_eq!(
+            manager.try_add_window(Window::new(Rect::new(6, 3, 4, 2))),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn apply_theme_restyles_borders() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 10, 5);
+        let mut win = Window::new(Rect::new(1, 1, 6, 3));
+        win.draw_border("Hi");
+        manager.add_window(win);
+
+        let red = Theme::default().border(Style::default().fg(Color::Red));
+        manager.apply_theme(&red);
+        let screen = manager.screenshot();
+        let corner = &screen.content[screen.index_of(1, 1)];
+        assert_eq!(corner.symbol, "╭");
+        assert_eq!(corner.fg, Color::Red);
+        assert_eq!(screen.content[screen.index_of(2, 1)].fg, Color::Reset);
+
+        manager.apply_theme(&Theme::default());
+        let screen = manager.screenshot();
+        assert_eq!(screen.content[screen.index_of(1, 1)].fg, Color::Reset);
+    }
+
+    #[test]
+    fn hover_sends_local_position_once_per_cell() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let below = manager.add_window(Window::new(Rect::new(0, 0, 10, 10)));
+        let above = manager.add_window(Window::new(Rect::new(5, 5, 10, 10)));
+
+        manager.handle_mouse(mouse(MouseEventKind::Moved, 7, 8));
+        manager.handle_mouse(mouse(MouseEventKind::Moved, 7, 8));
+        assert_eq!(manager.hovered_window(), Some(above));
+        assert_eq!(manager.windows[above].hovered(), Some((2, 3)));
+        assert_eq!(
+            manager.windows[above].take_events(),
+            vec![window::Event::Hover((2, 3))]
+        );
+
+        manager.handle_mouse(mouse(MouseEventKind::Moved, 8, 8));
+        manager.handle_mouse(mouse(MouseEventKind::Moved, 9, 8));
+        assert_eq!(
+            manager.windows[above].take_events(),
+            vec![window::Event::Hover((4, 3))]
+        );
+
+        manager.handle_mouse(mouse(MouseEventKind::Moved, 1, 1));
+        assert_eq!(manager.hovered_window(), Some(below));
+        assert_eq!(manager.windows[above].hovered(), None);
+        assert_eq!(
+            manager.windows[below].take_events(),
+            vec![window::Event::Hover((1, 1))]
+        );
+    }
+
+    #[test]
+    fn raise_and_lower_keep_the_hovered_window() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        manager.add_window(Window::new(Rect::new(0, 0, 5, 5)));
+        manager.add_window(Window::new(Rect::new(10, 0, 5, 5)));
+        manager.focus_window(0);
+        manager.handle_mouse(mouse(MouseEventKind::Moved, 11, 1));
+        assert_eq!(manager.hovered_window(), Some(1));
+
+        manager.handle_key(KeyEvent::new(KeyCode::PageUp, KeyModifiers::NONE));
+        assert_eq!(manager.current_window, 1);
+        assert_eq!(manager.hovered_window(), Some(0));
+        assert_eq!(manager.windows[0].hovered(), Some((1, 1)));
+
+        manager.handle_mouse(mouse(MouseEventKind::Moved, 30, 10));
+        assert_eq!(manager.hovered_window(), None);
+        assert!(manager.windows.iter().all(|win| win.hovered().is_none()));
+
+        manager.handle_mouse(mouse(MouseEventKind::Moved, 1, 1));
+        manager.handle_key(KeyEvent::new(KeyCode::PageDown, KeyModifiers::NONE));
+        assert_eq!(manager.current_window, 0);
+        assert_eq!(manager.hovered_window(), Some(0));
+        assert_eq!(manager.windows[0].hovered(), Some((1, 1)));
+    }
+
+    #[test]
+    fn resize_handle_resizes_focused_window() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        manager.add_window(Window::new(Rect::new(0, 0, 10, 10)));
+        let index = manager.add_window(Window::new(Rect::new(5, 5, 10, 6)));
+        manager.focus_window(index);
+
+        manager.draw_windows();
+        let screen = &manager.buffers[manager.current];
+        assert_eq!(screen.content[screen.index_of(14, 10)].symbol, " ");
+
+        manager.set_show_resize_handles(true);
+        manager.draw_windows();
+        let screen = &manager.buffers[manager.current];
+        assert_eq!(
+            screen.content[screen.index_of(14, 10)].symbol,
+            RESIZE_HANDLE
+        );
+
+        let left = MouseButton::Left;
+        manager.handle_mouse(mouse(MouseEventKind::Down(left), 14, 10));
+        manager.handle_mouse(mouse(MouseEventKind::Drag(left), 20, 12));
+        manager.handle_mouse(mouse(MouseEventKind::Up(left), 20, 12));
+        assert_eq!(manager.windows[index].rect(), Rect::new(5, 5, 16, 8));
+        // The drag itself isn't forwarded, only the resulting resize.
+        assert_eq!(
+            manager.windows[index].take_events(),
+            vec![window::Event::Focus(true), window::Event::Resize(16, 8)]
+        );
+
+        manager.draw_windows();
+        let screen = &manager.buffers[manager.current];
+        assert_eq!(
+            screen.content[screen.index_of(20, 12)].symbol,
+            RESIZE_HANDLE
+        );
+    }
+
+    #[test]
+    fn resize_handle_hidden_under_other_windows() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let below = manager.add_window(Window::new(Rect::new(0, 0, 10, 10)));
+        let above = manager.add_window(Window::new(Rect::new(5, 5, 10, 10)));
+        manager.focus_window(below);
+        manager.set_show_resize_handles(true);
+
+        manager.draw_windows();
+        let screen = &manager.buffers[manager.current];
+        assert_eq!(screen.content[screen.index_of(9, 9)].symbol, " ");
+
+        let left = MouseButton::Left;
+        manager.handle_mouse(mouse(MouseEventKind::Down(left), 9, 9));
+        manager.handle_mouse(mouse(MouseEventKind::Drag(left), 20, 12));
+        manager.handle_mouse(mouse(MouseEventKind::Up(left), 20, 12));
+        assert_eq!(manager.windows[below].rect(), Rect::new(0, 0, 10, 10));
+        assert_eq!(manager.current_window, above);
+    }
+
+    #[test]
+    fn frame_pacing_accounts_for_render_time() {
+        let clock = crate::clock::MockClock::new();
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 5)
+            .with_frame_time(Duration::from_millis(10));
+        manager.set_clock(clock.clone());
+        let ms = Duration::from_millis;
+
+        let mut next_frame = clock.now();
+        let mut frames = Vec::new();
+        for _ in 0..10 {
+            match manager.pace_frame(&mut next_frame).unwrap() {
+                // Every frame takes 4ms to render.
+                None => {
+                    frames.push(manager.clock.now());
+                    clock.advance(ms(4));
+                }
+                Some(timeout) => clock.advance(timeout),
+            }
+        }
+        let cadence: Vec<_> = frames.windows(2).map(|f| f[1] - f[0]).collect();
+        assert_eq!(cadence, vec![ms(10); 4]);
+
+        // A frame taking longer than the frame time is followed by the next one right away.
+        assert_eq!(manager.pace_frame(&mut next_frame).unwrap(), None);
+        clock.advance(ms(25));
+        assert_eq!(manager.pace_frame(&mut next_frame).unwrap(), None);
+        assert_eq!(manager.pace_frame(&mut next_frame).unwrap(), Some(ms(10)));
+    }
+
+    #[test]
+    fn composite_into_custom_buffer() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let mut below = Window::new(Rect::new(0, 0, 4, 2));
+        below.set_string(0, 0, "aaaa", Style::default());
+        let mut above = Window::new(Rect::new(2, 0, 4, 2));
+        above.set_string(0, 0, "bbbb", Style::default());
+        manager.add_window(below);
+        manager.add_window(above);
+        manager.add_window(Window::new(Rect::new(30, 10, 4, 2)));
+
+        let mut target = Buffer::filled(8, 2, Cell::default().set_char('.'));
+        manager.composite_into(&mut target);
+        let row: String = target.content[..8]
+            .iter()
+            .map(|c| c.symbol.as_str())
+            .collect();
+        assert_eq!(row, "aabbbb..");
+    }
+
+    #[test]
+    fn idle_frames_write_nothing() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 5);
+        manager.set_render_on_idle(true);
+        let index = manager.add_window(Window::new(Rect::new(0, 0, 10, 3)));
+        manager
+            .window_mut(index)
+            .unwrap()
+            .set_string(0, 0, "hi", Style::default());
+
+        manager.tick().unwrap();
+        assert!(!manager.backend().is_empty());
+        assert!(!manager.needs_redraw());
+        manager.backend_mut().clear();
+
+        for _ in 0..5 {
+            manager.tick().unwrap();
+        }
+        assert!(manager.backend().is_empty());
+
+        manager
+            .window_mut(index)
+            .unwrap()
+            .set_string(0, 1, "x", Style::default());
+        assert!(manager.needs_redraw());
+        manager.tick().unwrap();
+        assert!(!manager.backend().is_empty());
+    }
+
+    #[test]
+    fn key_repeat_is_opt_in() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let index = manager.add_window(Window::new(Rect::new(0, 0, 10, 10)));
+        let key = |kind| KeyEvent::new_with_kind(KeyCode::Down, KeyModifiers::NONE, kind);
+
+        for kind in [
+            KeyEventKind::Press,
+            KeyEventKind::Repeat,
+            KeyEventKind::Release,
+        ] {
+            manager.handle_key(key(kind));
+        }
+        assert_eq!(
+            manager.windows[index].take_events(),
+            vec![window::Event::Key(key(KeyEventKind::Press))]
+        );
+
+        manager.windows[index].set_key_repeat(true);
+        for kind in [
+            KeyEventKind::Press,
+            KeyEventKind::Repeat,
+            KeyEventKind::Release,
+        ] {
+            manager.handle_key(key(kind));
+        }
+        assert_eq!(
+            manager.windows[index].take_events(),
+            vec![
+                window::Event::Key(key(KeyEventKind::Press)),
+                window::Event::Key(key(KeyEventKind::Repeat)),
+            ]
+        );
+    }
+
+    #[test]
+    fn manager_keys_ignore_repeats() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        for _ in 0..3 {
+            manager.add_window(Window::new(Rect::new(0, 0, 10, 10)));
+        }
+        let key = |kind| KeyEvent::new_with_kind(KeyCode::Char('w'), KeyModifiers::CONTROL, kind);
+
+        for kind in [
+            KeyEventKind::Press,
+            KeyEventKind::Repeat,
+            KeyEventKind::Repeat,
+            KeyEventKind::Release,
+        ] {
+            manager.handle_key(key(kind));
+        }
+        assert_eq!(manager.windows().len(), 2);
+        assert!(!manager
+            .windows
+            .iter_mut()
+            .flat_map(Window::take_events)
+            .any(|event| matches!(event, window::Event::Key(_))));
+    }
+
+    #[test]
+    fn windows_overlapping() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let a = manager.add_window(Window::new(Rect::new(0, 0, 10, 10)));
+        let edge = manager.add_window(Window::new(Rect::new(10, 0, 10, 10)));
+        let b = manager.add_window(Window::new(Rect::new(5, 5, 10, 10)));
+
+        assert_eq!(manager.windows_overlapping(a), vec![b]);
+        assert_eq!(manager.windows_overlapping(edge), vec![b]);
+        assert_eq!(manager.windows_overlapping(b), vec![a, edge]);
+        assert!(manager.windows_overlapping(7).is_empty());
+    }
+
+    #[test]
+    fn minimized_windows_cover_nothing() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let lower = manager.add_window(Window::new(Rect::new(2, 2, 10, 8)));
+        let upper = manager.add_window(Window::new(Rect::new(0, 0, 20, 6)));
+        manager.window_mut(upper).unwrap().minimize();
+
+        assert_eq!(manager.visible_region(lower), vec![Rect::new(2, 2, 10, 8)]);
+        assert!(manager.windows_overlapping(lower).is_empty());
+    }
+
+    #[test]
+    fn spawn_from_template() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let mut template = Window::new(Rect::new(0, 0, 8, 4));
+        template.set_border_style(Style::default().fg(Color::Green));
+        template.set_theme(&Theme::default().title(Style::default().fg(Color::Yellow)));
+        template.set_padding((0, 1, 0, 1));
+        template.draw_border("Toast");
+        template.set_string(2, 1, "old", Style::default());
+
+        let a = manager.spawn_from(&template, Rect::new(20, 0, 10, 3));
+        let b = manager.spawn_from(&template, Rect::new(20, 5, 10, 3));
+        manager
+            .window_mut(a)
+            .unwrap()
+            .set_string(2, 1, "one", Style::default());
+        manager
+            .window_mut(b)
+            .unwrap()
+            .set_string(2, 1, "two", Style::default());
+
+        for (index, content) in [(a, "one"), (b, "two")] {
+            let win = &manager.windows[index];
+            let lines = win.buffer().to_lines();
+            assert_eq!(lines[0], "╭Toast───╮");
+            assert_eq!(lines[1], format!("│ {content}    │"));
+            assert_eq!(win.buffer().content[0].fg, Color::Green);
+            assert_eq!(win.buffer().content[1].fg, Color::Yellow);
+            assert_eq!(win.content_area(), Rect::new(2, 1, 6, 1));
+        }
+        assert_eq!(manager.windows[b].pos(), (20, 5));
+    }
+
+    #[test]
+    fn resize_resets_buffers() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 80, 24);
+        let index = manager.add_window(Window::new(Rect::new(0, 0, 10, 5)));
+        manager.windows[index].set_string(0, 0, "hi", Style::default());
+        manager.tick().unwrap();
+        manager.tick().unwrap();
+
+        manager.resize(100, 40);
+        for buffer in &manager.buffers {
+            assert_eq!((buffer.width, buffer.height), (100, 40));
+            assert_eq!(buffer.content.len(), 100 * 40);
+            assert!(buffer.content.iter().all(|cell| *cell == Cell::default()));
+        }
+        assert!(manager.needs_redraw());
+
+        let mut clear = Vec::new();
+        queue!(clear, Clear(ClearType::All)).unwrap();
+        manager.update_screen().unwrap();
+        assert!(manager.backend().starts_with(&clear));
+        assert!(String::from_utf8_lossy(manager.backend()).contains("hi"));
+
+        manager.backend_mut().clear();
+        manager.windows[index].set_string(0, 1, "x", Style::default());
+        manager.update_screen().unwrap();
+        assert!(!manager.backend().starts_with(&clear));
+    }
+
+    #[test]
+    fn minimized_windows_are_not_drawn() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 10, 3);
+        let mut below = Window::new(Rect::new(0, 0, 10, 3));
+        below.set_string(0, 0, "below", Style::default());
+        manager.add_window(below);
+        let mut above = Window::new(Rect::new(0, 0, 10, 3));
+        above.set_string(0, 0, "above", Style::default());
+        let index = manager.add_window(above);
+        manager.focus_window(index);
+
+        let ctrl_z = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        manager.handle_key(ctrl_z);
+        assert!(manager.windows[index].is_minimized());
+        manager.draw_windows();
+        assert_eq!(manager.screenshot().to_lines()[0], "below     ");
+        assert_eq!(manager.buffers[manager.current].to_lines()[0], "below     ");
+        assert_eq!(manager.window_at(1, 0), Some(0));
+
+        manager.handle_key(ctrl_z);
+        manager.draw_windows();
+        assert_eq!(manager.buffers[manager.current].to_lines()[0], "above     ");
+    }
+
+    #[test]
+    fn close_window_keeps_focus_in_range() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        for i in 0..3 {
+            let mut win = Window::new(Rect::new(i, i, 5, 5));
+            win.set_title(i.to_string());
+            manager.add_window(win);
+        }
+        manager.focus_window(1);
+
+        manager.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(manager.windows.len(), 2);
+        assert_eq!(manager.focused_window().unwrap().title(), Some("2"));
+
+        let closed = manager.close_window(1).unwrap();
+        assert_eq!(closed.title(), Some("2"));
+        assert_eq!(manager.focused_window().unwrap().title(), Some("0"));
+
+        assert!(manager.close_window(1).is_none());
+        manager.close_window(0);
+        assert!(manager.focused_window().is_none());
+    }
+
+    #[test]
+    fn focus_changes_send_focus_events() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let a = manager.add_window(Window::new(Rect::new(0, 0, 5, 5)));
+        let b = manager.add_window(Window::new(Rect::new(5, 0, 5, 5)));
+
+        manager.focus_next();
+        assert_eq!(
+            manager.windows[a].take_events(),
+            vec![window::Event::Focus(false)]
+        );
+        assert_eq!(
+            manager.windows[b].take_events(),
+            vec![window::Event::Focus(true)]
+        );
+
+        manager.focus_window(b);
+        assert!(manager.windows[b].take_events().is_empty());
+
+        manager.close_window(b);
+        assert_eq!(
+            manager.windows[a].take_events(),
+            vec![window::Event::Focus(true)]
+        );
+    }
+
+    #[test]
+    fn cursor_follows_focused_window() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let a = manager.add_window(Window::new(Rect::new(3, 4, 10, 5)));
+        let b = manager.add_window(Window::new(Rect::new(20, 2, 10, 5)));
+        manager.windows[a].set_cursor(2, 1);
+        manager.focus_window(a);
+        assert_eq!(manager.cursor_position(), Some((5, 5)));
+
+        manager.update_screen().unwrap();
+        let mut expected = Vec::new();
+        queue!(expected, MoveTo(5, 5), Show).unwrap();
+        assert!(manager.backend().ends_with(&expected));
+
+        manager.windows[a].set_cursor(10, 1);
+        assert_eq!(manager.cursor_position(), None);
+        manager.focus_window(b);
+        assert_eq!(manager.cursor_position(), None);
+
+        manager.backend_mut().clear();
+        manager.update_screen().unwrap();
+        let mut expected = Vec::new();
+        queue!(expected, Hide).unwrap();
+        assert!(manager.backend().starts_with(&expected));
+    }
+
+    #[test]
+    fn ascii_mode_replaces_border_glyphs() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 10);
+        manager.set_ascii(true);
+        let mut win = Window::new(Rect::new(1, 1, 6, 3));
+        win.draw_border("");
+        manager.add_window(win);
+        manager.update_screen().unwrap();
+
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        assert!(out.contains("+----+"));
+        assert!(out.contains('|'));
+        assert!(!out.contains(['╭', '╮', '╰', '╯', '─', '│']));
+    }
+
+    #[test]
+    fn toggling_ascii_repaints_the_screen() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 20, 10);
+        let mut win = Window::new(Rect::new(1, 1, 6, 3));
+        win.draw_border("");
+        manager.add_window(win);
+        manager.update_screen().unwrap();
+        assert!(String::from_utf8(manager.backend().clone())
+            .unwrap()
+            .contains("╭────╮"));
+
+        manager.backend_mut().clear();
+        manager.set_ascii(true);
+        manager.update_screen().unwrap();
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        assert!(out.contains("+----+"));
+        assert!(!out.contains(['╭', '╮', '╰', '╯', '─', '│']));
+
+        manager.backend_mut().clear();
+        manager.set_ascii(true);
+        manager.update_screen().unwrap();
+        assert!(manager.backend().is_empty());
+
+        // Cells left blank by the new frame are cleared too.
+        manager.set_ascii(false);
+        manager.update_screen().unwrap();
+        manager.backend_mut().clear();
+        manager.close_window(0);
+        manager.set_ascii(true);
+        manager.update_screen().unwrap();
+        let mut expected = Vec::new();
+        queue!(expected, Clear(ClearType::All)).unwrap();
+        let expected = String::from_utf8(expected).unwrap();
+        let out = String::from_utf8(manager.backend().clone()).unwrap();
+        assert!(out.contains(&expected));
+    }
+
+    #[test]
+    fn keymap_remaps_quit() {
+        let mut manager = WindowManager::with_backend(Vec::new(), 40, 20);
+        let mut keymap = KeyMap::default();
+        keymap.unbind(Action::Quit);
+        keymap.bind(
+            KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+            Action::Quit,
+        );
+        manager.set_keymap(keymap);
+
+        manager.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(!manager.should_exit);
+        manager.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(manager.should_exit);
+    }
+}